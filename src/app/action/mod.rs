@@ -3,21 +3,120 @@ use std::path::PathBuf;
 use csscolorparser::Color;
 use either::Either;
 
+use crate::{
+    app::config::{
+        blend::BlendMode, brush::BrushShape, mirror::MirrorMode, pattern::Pattern, picker::Field,
+        selection::Selection, tool::Tool,
+    },
+    drawing::{
+        quantize::Method,
+        transform::{Anchor, Transform},
+    },
+};
+
 #[derive(Debug, Clone)]
 pub enum Action {
     /// Quit the application
     Quit,
-    Save(Option<PathBuf>),
+    /// Write the drawing, `true` to overwrite a file that failed to load.
+    Save(Option<PathBuf>, bool),
+    /// Export the drawing in the format of the path extension.
+    Export(PathBuf),
     EnterCommandMode,
     EnterNormalMode,
     CommandPush(char),
     CommandPop,
-    /// Resize the drawing.
-    Resize(usize, usize),
+    /// Open color picker dialog.
+    EnterPickerMode,
+    /// Move picker focus by the offset.
+    PickerFocus(isize),
+    /// Adjust focused picker field by steps.
+    PickerAdjust(i32),
+    /// Set picker field to the position in `[0, 1]`.
+    PickerSet(Field, f32),
+    /// Type into picker hex field.
+    PickerInput(char),
+    PickerErase,
+    /// Apply picked color and close the picker.
+    PickerCommit,
+    /// Paint with the keyboard cursor.
+    EnterCursorMode,
+    /// Append a digit to the count of cursor motion.
+    CursorCount(usize),
+    /// Move the cursor by the offset.
+    MoveCursor(isize, isize),
+    /// Paint under the cursor, or fill the selection.
+    PaintCursor,
+    /// Erase under the cursor, or the selection.
+    EraseCursor,
+    /// Start or end visual selection from the cursor.
+    ToggleVisual,
+    /// Select a region, `None` to clear the selection.
+    Select(Option<Selection>),
+    /// Resize the drawing, keeping the anchor point in place.
+    Resize(usize, usize, Anchor),
     /// Erase the drawing.
     Erase,
+    Undo,
+    Redo,
+    /// Transform the selection, or the whole drawing when nothing is selected.
+    Transform(Transform),
     GetColor,
     SetColor(Either<Color, u8>),
+    /// Set secondary color, `None` to report the current one.
+    SecondaryColor(Option<Color>),
+    /// Swap primary and secondary color.
+    SwapColors,
+    SetTool(Tool),
+    SetPattern(Pattern),
+    /// Set blend mode, `None` to report the current one.
+    Blend(Option<BlendMode>),
+    /// Report the current brush.
+    GetBrush,
+    SetBrushShape(BrushShape),
+    SetBrushSize(usize),
+    /// Use region `(x, y, width, height)` of the drawing as brush.
+    CaptureBrush(usize, usize, usize, usize),
+    /// Load a drawing or image file as brush.
+    LoadBrush(PathBuf),
+    /// Enable pixel-perfect strokes, `None` to toggle.
+    PixelPerfect(Option<bool>),
+    /// Choose lower half of cell in mouse cell mode, `None` to toggle.
+    LowerHalf(Option<bool>),
+    /// Set mirror mode, `None` to report the current one.
+    Mirror(Option<MirrorMode>),
+    /// Set mirror axis in half pixels, `None` for the drawing center.
+    MirrorAxis(Option<(usize, usize)>),
+    /// Enable tile mode, `None` to toggle.
+    Tile(Option<bool>),
+    /// Show tile grid, `None` to toggle.
+    Grid(Option<bool>),
+    /// Set tile size of the grid and show it.
+    GridSpacing(usize),
+    /// Set tint of grid and guides.
+    GridColor(Color),
+    /// Toggle vertical guide at column.
+    GuideColumn(usize),
+    /// Toggle horizontal guide at row.
+    GuideRow(usize),
+    /// Remove all guides.
+    ClearGuides,
+    /// Load an image as reference under the drawing.
+    LoadReference(PathBuf),
+    /// Set opacity of the reference.
+    ReferenceOpacity(f32),
+    /// Set drawing pixels per reference pixel, `None` to fit the drawing.
+    ReferenceScale(Option<f32>),
+    /// Move the reference top left corner to drawing position.
+    ReferenceOffset(isize, isize),
+    /// Remove the reference.
+    ClearReference,
+    /// Reduce the drawing to at most given number of colors.
+    Quantize(usize, Method),
+    /// Replace colors by the nearest palette color, dithered when `true`.
+    Remap(Vec<[u8; 3]>, bool),
+    /// Load palette file and remap the drawing to it, dithered when `true`.
+    LoadPalette(PathBuf, bool),
     /// Execute JavaScript.
     Execute(String),
 }
@@ -0,0 +1,45 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+use csscolorparser::Color;
+
+use crate::drawing::color::ColorExt;
+
+/// How painted color is combined with the existing pixel.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Alpha composite painted color over the pixel.
+    #[default]
+    Over,
+    /// Overwrite the pixel, including alpha.
+    Replace,
+}
+
+impl BlendMode {
+    /// Result of painting `src` onto `dst`.
+    pub fn apply(self, src: &Color, dst: &Color) -> Color {
+        match self {
+            BlendMode::Over => src.over(dst),
+            BlendMode::Replace => src.clone(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BlendMode::Over => "over",
+            BlendMode::Replace => "replace",
+        }
+    }
+}
+
+impl FromStr for BlendMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "over" => BlendMode::Over,
+            "replace" => BlendMode::Replace,
+            _ => bail!("unknown blend mode: {}", s),
+        })
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{Result, bail};
+use csscolorparser::Color;
+
+use crate::drawing::Drawing;
+
+/// Shape of the brush used by painting and erasing.
+#[derive(Default, Clone, Debug)]
+pub enum BrushShape {
+    #[default]
+    Square,
+    Round,
+    /// Stamp of pixels, transparent pixels are not painted.
+    Custom(Drawing),
+}
+
+pub struct Brush {
+    shape: BrushShape,
+    /// Width/Height of square and round brush.
+    size: usize,
+    /// Offsets covered by the brush, computed when the brush changes.
+    footprint: Vec<((isize, isize), Option<Color>)>,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        let mut brush = Self {
+            shape: Default::default(),
+            size: 1,
+            footprint: vec![],
+        };
+        brush.update_footprint();
+        brush
+    }
+}
+
+impl Brush {
+    pub const MAX_SIZE: usize = 16;
+    /// Largest width/height of custom brush, which is stamped pixel by pixel.
+    pub const MAX_CUSTOM_SIZE: usize = 64;
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Set shape, custom brush must be at most [`Self::MAX_CUSTOM_SIZE`].
+    pub fn set_shape(&mut self, shape: BrushShape) -> Result<()> {
+        if let BrushShape::Custom(stamp) = &shape
+            && (stamp.width > Self::MAX_CUSTOM_SIZE || stamp.height > Self::MAX_CUSTOM_SIZE)
+        {
+            bail!(
+                "brush {}x{} is larger than {}x{}",
+                stamp.width,
+                stamp.height,
+                Self::MAX_CUSTOM_SIZE,
+                Self::MAX_CUSTOM_SIZE
+            );
+        }
+        self.shape = shape;
+        self.update_footprint();
+        Ok(())
+    }
+
+    /// Set size of square and round brush, clamped to `1..=MAX_SIZE`.
+    pub fn set_size(&mut self, size: usize) {
+        self.size = size.clamp(1, Self::MAX_SIZE);
+        self.update_footprint();
+    }
+
+    /// Offsets covered by the brush relative to the cursor,
+    /// with the stamp color for custom brush.
+    pub fn footprint(&self) -> &[((isize, isize), Option<Color>)] {
+        &self.footprint
+    }
+
+    fn update_footprint(&mut self) {
+        let (width, height) = self.dimensions();
+        let (start_x, start_y) = (-(width as isize / 2), -(height as isize / 2));
+
+        let mut footprint = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let color = match &self.shape {
+                    BrushShape::Square => None,
+                    BrushShape::Round => {
+                        let radius = self.size as f32 / 2.;
+                        let dx = x as f32 + 0.5 - radius;
+                        let dy = y as f32 + 0.5 - radius;
+                        if dx * dx + dy * dy > radius * radius {
+                            continue;
+                        }
+                        None
+                    }
+                    BrushShape::Custom(stamp) => match stamp.pixel(x, y) {
+                        Some(color) if color.a > 0. => Some(color.clone()),
+                        _ => continue,
+                    },
+                };
+                footprint.push(((start_x + x as isize, start_y + y as isize), color));
+            }
+        }
+        self.footprint = footprint;
+    }
+
+    /// Width and height covered by the brush.
+    pub fn dimensions(&self) -> (usize, usize) {
+        match &self.shape {
+            BrushShape::Square | BrushShape::Round => (self.size, self.size),
+            BrushShape::Custom(stamp) => (stamp.width, stamp.height),
+        }
+    }
+
+    /// Short description shown in status bar.
+    pub fn label(&self) -> String {
+        match &self.shape {
+            BrushShape::Square => format!("■{}", self.size),
+            BrushShape::Round => format!("●{}", self.size),
+            BrushShape::Custom(stamp) => format!("◆{}x{}", stamp.width, stamp.height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_footprint() {
+        let mut brush = Brush::default();
+        assert_eq!(brush.footprint().len(), 1);
+        brush.set_size(3);
+        assert_eq!(brush.footprint().len(), 9);
+        brush.set_shape(BrushShape::Round).unwrap();
+        assert_eq!(brush.footprint().len(), 9);
+
+        let mut stamp = Drawing::new(2, 1);
+        stamp.pixels[1] = Color::from_rgba8(255, 0, 0, 255);
+        brush.set_shape(BrushShape::Custom(stamp)).unwrap();
+        assert_eq!(brush.footprint().len(), 1);
+
+        let large = Drawing::new(Brush::MAX_CUSTOM_SIZE + 1, 1);
+        assert!(brush.set_shape(BrushShape::Custom(large)).is_err());
+        assert_eq!(brush.dimensions(), (2, 1));
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeSet;
+
+/// Tile grid and guide lines shown over the canvas, separate from the transparency grid.
+pub struct Grid {
+    pub enabled: bool,
+    /// Width/Height of each tile.
+    pub spacing: usize,
+    /// Tint of grid and guide lines.
+    pub color: [u8; 3],
+    /// Columns of vertical guides.
+    pub columns: BTreeSet<usize>,
+    /// Rows of horizontal guides.
+    pub rows: BTreeSet<usize>,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing: 8,
+            color: [0, 160, 255],
+            columns: BTreeSet::new(),
+            rows: BTreeSet::new(),
+        }
+    }
+}
+
+impl Grid {
+    /// Whether `(x, y)` is on the first row or column of a tile.
+    pub fn on_line(&self, x: usize, y: usize) -> bool {
+        self.enabled && (x.is_multiple_of(self.spacing) || y.is_multiple_of(self.spacing))
+    }
+
+    /// Whether `(x, y)` is on a user guide.
+    pub fn on_guide(&self, x: usize, y: usize) -> bool {
+        self.columns.contains(&x) || self.rows.contains(&y)
+    }
+
+    /// Add guide at column, or remove it when present.
+    pub fn toggle_column(&mut self, x: usize) {
+        if !self.columns.remove(&x) {
+            self.columns.insert(x);
+        }
+    }
+
+    /// Add guide at row, or remove it when present.
+    pub fn toggle_row(&mut self, y: usize) {
+        if !self.rows.remove(&y) {
+            self.rows.insert(y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grid_lines() {
+        let mut grid = Grid {
+            spacing: 4,
+            ..Default::default()
+        };
+        assert!(!grid.on_line(0, 0));
+        grid.enabled = true;
+        assert!(grid.on_line(4, 1));
+        assert!(grid.on_line(1, 8));
+        assert!(!grid.on_line(1, 3));
+
+        grid.toggle_column(2);
+        assert!(grid.on_guide(2, 5));
+        grid.toggle_column(2);
+        assert!(!grid.on_guide(2, 5));
+    }
+}
@@ -0,0 +1,139 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+
+/// Which axes painting is mirrored around.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MirrorMode {
+    #[default]
+    Off,
+    /// Mirror left and right around a vertical axis.
+    X,
+    /// Mirror top and bottom around a horizontal axis.
+    Y,
+    /// Mirror around both axes.
+    XY,
+}
+
+impl MirrorMode {
+    /// The mode toggled to from the status bar.
+    pub fn next(self) -> Self {
+        match self {
+            MirrorMode::Off => MirrorMode::X,
+            MirrorMode::X => MirrorMode::Y,
+            MirrorMode::Y => MirrorMode::XY,
+            MirrorMode::XY => MirrorMode::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MirrorMode::Off => "off",
+            MirrorMode::X => "x",
+            MirrorMode::Y => "y",
+            MirrorMode::XY => "xy",
+        }
+    }
+
+    fn horizontal(self) -> bool {
+        matches!(self, MirrorMode::X | MirrorMode::XY)
+    }
+
+    fn vertical(self) -> bool {
+        matches!(self, MirrorMode::Y | MirrorMode::XY)
+    }
+}
+
+impl FromStr for MirrorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "off" => MirrorMode::Off,
+            "x" => MirrorMode::X,
+            "y" => MirrorMode::Y,
+            "xy" | "yx" => MirrorMode::XY,
+            _ => bail!("unknown mirror mode: {}", s),
+        })
+    }
+}
+
+/// Symmetry painting setting.
+#[derive(Default)]
+pub struct Mirror {
+    pub mode: MirrorMode,
+    /// Position of the axes in half pixels, `None` for the drawing center.
+    ///
+    /// An even value `2n` is the edge between pixel `n - 1` and `n`,
+    /// an odd value `2n + 1` is the center of pixel `n`.
+    pub axis: Option<(usize, usize)>,
+}
+
+impl Mirror {
+    /// The axes in half pixels for a drawing of given size.
+    pub fn axis(&self, width: usize, height: usize) -> (usize, usize) {
+        self.axis.unwrap_or((width, height))
+    }
+
+    /// All positions painted when painting at `(x, y)`, including itself.
+    pub fn points(&self, x: usize, y: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let (axis_x, axis_y) = self.axis(width, height);
+        let flip = |v: usize, axis: usize, max: usize| {
+            axis.checked_sub(v + 1).filter(|flipped| *flipped < max)
+        };
+
+        let mut points = vec![(x, y)];
+        if self.mode.horizontal()
+            && let Some(x) = flip(x, axis_x, width)
+        {
+            points.push((x, y));
+        }
+        if self.mode.vertical() {
+            for (x, y) in points.clone() {
+                if let Some(y) = flip(y, axis_y, height) {
+                    points.push((x, y));
+                }
+            }
+        }
+        points.sort_unstable();
+        points.dedup();
+        points
+    }
+
+    /// Whether the pixel is next to an active axis and should show the guide.
+    pub fn on_guide(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        let (axis_x, axis_y) = self.axis(width, height);
+        let near = |v: usize, axis: usize| 2 * v <= axis && axis <= 2 * v + 2;
+        (self.mode.horizontal() && near(x, axis_x)) || (self.mode.vertical() && near(y, axis_y))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_points_around_center() {
+        let mirror = Mirror {
+            mode: MirrorMode::XY,
+            axis: None,
+        };
+        assert_eq!(
+            mirror.points(1, 2, 8, 8),
+            vec![(1, 2), (1, 5), (6, 2), (6, 5)]
+        );
+    }
+
+    #[test]
+    fn test_points_on_odd_axis() {
+        let mirror = Mirror {
+            mode: MirrorMode::X,
+            axis: Some((5, 0)),
+        };
+        // the center pixel of the axis maps onto itself
+        assert_eq!(mirror.points(2, 0, 8, 8), vec![(2, 0)]);
+        assert_eq!(mirror.points(0, 0, 8, 8), vec![(0, 0), (4, 0)]);
+        // mirrored position outside of the drawing is dropped
+        assert_eq!(mirror.points(7, 0, 8, 8), vec![(7, 0)]);
+    }
+}
@@ -2,25 +2,73 @@ use std::collections::VecDeque;
 
 use csscolorparser::Color;
 
-use crate::app::config::{mode::Mode, transparency_grid::TransparencyGrid};
+use crate::{
+    app::config::{
+        blend::BlendMode, brush::Brush, grid::Grid, mirror::Mirror, mode::Mode, pattern::Pattern,
+        reference::Reference, selection::Selection, tool::Tool,
+        transparency_grid::TransparencyGrid,
+    },
+    drawing::Drawing,
+    utils::{color_depth::ColorDepth, mouse::MouseMode},
+};
 
+pub mod blend;
+pub mod brush;
+pub mod grid;
+pub mod mirror;
 pub mod mode;
+pub mod pattern;
+pub mod picker;
+pub mod reference;
+pub mod selection;
+pub mod tool;
 pub mod transparency_grid;
 
 /// App runtime config.
 pub struct Config {
     /// Current color.
     pub color: Color,
+    /// Secondary color used by patterns and gradient.
+    pub secondary_color: Color,
     /// Previously used colors.
     pub color_history: VecDeque<Color>,
     pub transparency_grid: TransparencyGrid,
+    /// Tile grid and guides.
+    pub grid: Grid,
     pub mode: Mode,
+    /// Symmetry painting.
+    pub mirror: Mirror,
+    /// Brush used by painting and erasing.
+    pub brush: Brush,
+    /// Remove L-shaped corners from strokes of one pixel brush.
+    pub pixel_perfect: bool,
+    pub tool: Tool,
+    /// Pattern of pencil painting.
+    pub pattern: Pattern,
+    /// How painting combines with existing pixels.
+    pub blend: BlendMode,
+    /// Colors supported by the terminal.
+    pub color_depth: ColorDepth,
+    /// Dither canvas when the terminal has limited colors.
+    pub dither_colors: bool,
+    pub mouse_mode: MouseMode,
+    /// Paint lower half of cell in mouse cell mode.
+    pub lower_half: bool,
+    /// Preview the drawing repeated around itself, painting wraps across edges.
+    pub tile: bool,
+    /// Pixel cursor of keyboard painting.
+    pub cursor: (usize, usize),
+    /// Selected region, painted and captured as a whole.
+    pub selection: Option<Selection>,
+    /// Image shown under the drawing for tracing.
+    pub reference: Option<Reference>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             color: Color::from_rgba8(0, 0, 0, 255),
+            secondary_color: Color::from_rgba8(255, 255, 255, 255),
             color_history: {
                 let mut v = VecDeque::new();
                 v.push_back(Color::from_rgba8(255, 255, 255, 255));
@@ -32,12 +80,46 @@ impl Default for Config {
                 v
             },
             transparency_grid: Default::default(),
+            grid: Default::default(),
             mode: Default::default(),
+            mirror: Default::default(),
+            brush: Default::default(),
+            pixel_perfect: false,
+            tool: Default::default(),
+            pattern: Default::default(),
+            blend: Default::default(),
+            color_depth: Default::default(),
+            dither_colors: false,
+            mouse_mode: Default::default(),
+            lower_half: false,
+            tile: false,
+            cursor: (0, 0),
+            selection: None,
+            reference: None,
         }
     }
 }
 
 impl Config {
+    /// Size of the displayed canvas in pixels, three times the drawing in tile mode.
+    pub fn view_size(&self, drawing: &Drawing) -> (usize, usize) {
+        let repeat = if self.tile { 3 } else { 1 };
+        (drawing.width * repeat, drawing.height * repeat)
+    }
+
+    /// Drawing position displayed at canvas position, wrapping around in tile mode.
+    pub fn wrap(&self, drawing: &Drawing, x: usize, y: usize) -> (usize, usize) {
+        if self.tile {
+            (x % drawing.width, y % drawing.height)
+        } else {
+            (x, y)
+        }
+    }
+
+    pub fn swap_colors(&mut self) {
+        std::mem::swap(&mut self.color, &mut self.secondary_color);
+    }
+
     pub fn set_color(&mut self, color: Color) {
         let color_history = &mut self.color_history;
 
@@ -1,3 +1,5 @@
+use crate::app::config::picker::PickerState;
+
 #[derive(Default, Clone, Debug)]
 
 pub enum Mode {
@@ -5,4 +7,47 @@ pub enum Mode {
     Normal,
     /// Colon and input command.
     Command(String),
+    /// Color picker dialog.
+    Picker(PickerState),
+    /// Keyboard painting with the pixel cursor.
+    Cursor {
+        /// Count typed before a motion, like `5l`.
+        count: Option<usize>,
+        /// Start of visual selection.
+        anchor: Option<(usize, usize)>,
+    },
+}
+
+impl Mode {
+    /// Mode after executing `command` typed in command mode.
+    ///
+    /// Back to normal unless the command entered another mode, like `:picker`,
+    /// or prefilled another command, like `:canvas`.
+    pub fn after_execute(self, command: &str) -> Mode {
+        match self {
+            Mode::Command(current) if current == command => Mode::Normal,
+            mode => mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_after_execute() {
+        let mode = Mode::Command("w".to_string()).after_execute("w");
+        assert!(matches!(mode, Mode::Normal));
+
+        // `:canvas` leaves the prefilled resize prompt open
+        let mode = Mode::Command("resize 16x16 center".to_string()).after_execute("canvas");
+        assert!(matches!(mode, Mode::Command(command) if command == "resize 16x16 center"));
+
+        let mode = Mode::Cursor {
+            count: None,
+            anchor: None,
+        };
+        assert!(matches!(mode.after_execute("i"), Mode::Cursor { .. }));
+    }
 }
@@ -0,0 +1,69 @@
+use anyhow::{Result, bail};
+
+use crate::drawing::dither::bayer;
+
+/// How the pencil mixes primary and secondary colors.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    /// Primary color only.
+    #[default]
+    Solid,
+    /// Ordered dithering, `level` is the amount of secondary color.
+    Bayer {
+        order: usize,
+        level: f32,
+    },
+    Checker,
+    /// Horizontal lines.
+    HLines,
+    /// Vertical lines.
+    VLines,
+    Diagonal,
+}
+
+impl Pattern {
+    /// Parse pattern from command arguments, e.g. `bayer4 0.25`.
+    pub fn parse(name: &str, level: Option<&str>) -> Result<Self> {
+        let level = match level {
+            Some(level) => match level.parse::<f32>() {
+                Ok(level) if (0. ..=1.).contains(&level) => level,
+                _ => bail!("invalid pattern level: {}", level),
+            },
+            None => 0.5,
+        };
+        Ok(match name {
+            "solid" => Pattern::Solid,
+            "bayer2" => Pattern::Bayer { order: 2, level },
+            "bayer4" => Pattern::Bayer { order: 4, level },
+            "bayer8" => Pattern::Bayer { order: 8, level },
+            "checker" => Pattern::Checker,
+            "hlines" => Pattern::HLines,
+            "vlines" => Pattern::VLines,
+            "diagonal" => Pattern::Diagonal,
+            _ => bail!("unknown pattern: {}", name),
+        })
+    }
+
+    /// Whether the secondary color is used at `(x, y)`.
+    pub fn secondary(&self, x: usize, y: usize) -> bool {
+        match *self {
+            Pattern::Solid => false,
+            Pattern::Bayer { order, level } => bayer(order, x, y) < level,
+            Pattern::Checker => !(x + y).is_multiple_of(2),
+            Pattern::HLines => !y.is_multiple_of(2),
+            Pattern::VLines => !x.is_multiple_of(2),
+            Pattern::Diagonal => (x + y).is_multiple_of(4),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Pattern::Solid => "solid".to_string(),
+            Pattern::Bayer { order, level } => format!("bayer{} {:.2}", order, level),
+            Pattern::Checker => "checker".to_string(),
+            Pattern::HLines => "hlines".to_string(),
+            Pattern::VLines => "vlines".to_string(),
+            Pattern::Diagonal => "diagonal".to_string(),
+        }
+    }
+}
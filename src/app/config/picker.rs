@@ -0,0 +1,226 @@
+use csscolorparser::Color;
+
+/// Fields of the color picker, in display order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Hue,
+    Saturation,
+    Value,
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Hex,
+}
+
+impl Field {
+    pub const ALL: [Field; 8] = [
+        Field::Hue,
+        Field::Saturation,
+        Field::Value,
+        Field::Red,
+        Field::Green,
+        Field::Blue,
+        Field::Alpha,
+        Field::Hex,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Hue => "H",
+            Field::Saturation => "S",
+            Field::Value => "V",
+            Field::Red => "R",
+            Field::Green => "G",
+            Field::Blue => "B",
+            Field::Alpha => "A",
+            Field::Hex => "#",
+        }
+    }
+
+    /// Number of steps of the whole slider.
+    fn steps(self) -> f32 {
+        match self {
+            Field::Hue => 360.,
+            Field::Saturation | Field::Value => 100.,
+            _ => 255.,
+        }
+    }
+}
+
+/// State of the color picker dialog.
+#[derive(Clone, Debug)]
+pub struct PickerState {
+    /// Hue in degrees, saturation, value and alpha in `[0, 1]`.
+    ///
+    /// NOTE: kept as HSV so hue survives gray colors.
+    hsva: [f32; 4],
+    pub focus: Field,
+    /// Text of hex input field.
+    pub hex: String,
+}
+
+impl PickerState {
+    pub fn new(color: &Color) -> Self {
+        Self {
+            hsva: color.to_hsva(),
+            focus: Field::Hue,
+            hex: color.to_css_hex(),
+        }
+    }
+
+    /// The picked color.
+    pub fn color(&self) -> Color {
+        let [h, s, v, a] = self.hsva;
+        Color::from_hsva(h, s, v, a)
+    }
+
+    /// Position of the field value in `[0, 1]`.
+    pub fn fraction(&self, field: Field) -> f32 {
+        let color = self.color();
+        match field {
+            Field::Hue => self.hsva[0] / 360.,
+            Field::Saturation => self.hsva[1],
+            Field::Value => self.hsva[2],
+            Field::Red => color.r,
+            Field::Green => color.g,
+            Field::Blue => color.b,
+            Field::Alpha | Field::Hex => self.hsva[3],
+        }
+    }
+
+    /// Text of the field value.
+    pub fn value_text(&self, field: Field) -> String {
+        match field {
+            Field::Hex => self.hex.clone(),
+            Field::Hue => format!("{:.0}°", self.hsva[0]),
+            Field::Saturation | Field::Value => {
+                format!("{:.0}%", self.fraction(field) * 100.)
+            }
+            _ => format!("{:.0}", self.fraction(field) * 255.),
+        }
+    }
+
+    /// Color of the slider of `field` when set to `fraction`, for drawing the slider.
+    pub fn slider_color(&self, field: Field, fraction: f32) -> Color {
+        let mut state = self.clone();
+        state.set_fraction(field, fraction);
+        let mut color = state.color();
+        if field != Field::Alpha {
+            color.a = 1.;
+        }
+        color
+    }
+
+    /// Set value of `field` by its position in `[0, 1]`.
+    pub fn set_fraction(&mut self, field: Field, fraction: f32) {
+        let fraction = fraction.clamp(0., 1.);
+        match field {
+            Field::Hue => self.hsva[0] = fraction * 360.,
+            Field::Saturation => self.hsva[1] = fraction,
+            Field::Value => self.hsva[2] = fraction,
+            Field::Alpha => self.hsva[3] = fraction,
+            Field::Red | Field::Green | Field::Blue => {
+                let mut color = self.color();
+                match field {
+                    Field::Red => color.r = fraction,
+                    Field::Green => color.g = fraction,
+                    _ => color.b = fraction,
+                }
+                self.set_rgb(&color);
+            }
+            Field::Hex => {}
+        }
+        if field != Field::Hex {
+            self.hex = self.color().to_css_hex();
+        }
+    }
+
+    /// Move the focused field by steps, 360 steps for hue, 100 for saturation and value,
+    /// 255 for others.
+    pub fn adjust(&mut self, steps: i32) {
+        let field = self.focus;
+        if field == Field::Hex {
+            return;
+        }
+        let fraction = self.fraction(field) + steps as f32 / field.steps();
+        self.set_fraction(field, fraction);
+    }
+
+    /// Move focus to next (`delta > 0`) or previous field.
+    pub fn move_focus(&mut self, delta: isize) {
+        let len = Field::ALL.len() as isize;
+        let index = Field::ALL.iter().position(|f| *f == self.focus).unwrap() as isize;
+        self.focus = Field::ALL[(index + delta).rem_euclid(len) as usize];
+    }
+
+    /// Type into hex field, the color is updated when the text is valid.
+    ///
+    /// Typing from other field starts a new hex text.
+    pub fn input(&mut self, ch: char) {
+        if !ch.is_ascii_hexdigit() && ch != '#' {
+            return;
+        }
+        if self.focus != Field::Hex {
+            self.focus = Field::Hex;
+            self.hex.clear();
+        }
+        if ch != '#' || self.hex.is_empty() {
+            self.hex.push(ch);
+            self.sync_hex();
+        }
+    }
+
+    /// Delete last char of hex field.
+    pub fn erase(&mut self) {
+        self.hex.pop();
+        self.sync_hex();
+    }
+
+    fn sync_hex(&mut self) {
+        let digits = self.hex.trim_start_matches('#');
+        if matches!(digits.len(), 3 | 4 | 6 | 8)
+            && let Ok(color) = csscolorparser::parse(&format!("#{}", digits))
+        {
+            self.set_rgb(&color);
+            self.hsva[3] = color.a;
+        }
+    }
+
+    /// Update from RGB color, keeping hue (and saturation) when undefined.
+    fn set_rgb(&mut self, color: &Color) {
+        let [h, s, v, _] = color.to_hsva();
+        if v > 0. {
+            if s > 0. {
+                self.hsva[0] = h;
+            }
+            self.hsva[1] = s;
+        }
+        self.hsva[2] = v;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hue_kept_through_gray() {
+        let mut picker = PickerState::new(&Color::from_rgba8(255, 0, 0, 255));
+        picker.set_fraction(Field::Hue, 0.5);
+        picker.set_fraction(Field::Saturation, 0.);
+        assert_eq!(picker.color().to_rgba8(), [255, 255, 255, 255]);
+        picker.set_fraction(Field::Saturation, 1.);
+        assert_eq!(picker.color().to_rgba8(), [0, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_hex_input() {
+        let mut picker = PickerState::new(&Color::from_rgba8(0, 0, 0, 255));
+        for ch in "#00ff0080".chars() {
+            picker.input(ch);
+        }
+        assert_eq!(picker.focus, Field::Hex);
+        assert_eq!(picker.color().to_rgba8(), [0, 255, 0, 128]);
+    }
+}
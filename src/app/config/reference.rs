@@ -0,0 +1,74 @@
+use csscolorparser::Color;
+
+use crate::drawing::{Drawing, color::ColorExt};
+
+/// Image traced under the drawing, shown only on the canvas and never saved.
+pub struct Reference {
+    pub image: Drawing,
+    /// Opacity in `0..=1` over the transparency grid.
+    pub opacity: f32,
+    /// Drawing pixels per image pixel.
+    pub scale: f32,
+    /// Drawing position of the image top left corner.
+    pub offset: (isize, isize),
+}
+
+impl Reference {
+    pub fn new(image: Drawing) -> Self {
+        Self {
+            image,
+            opacity: 0.5,
+            scale: 1.,
+            offset: (0, 0),
+        }
+    }
+
+    /// Scale which fits the whole image inside a drawing of given size.
+    pub fn fit(&self, width: usize, height: usize) -> f32 {
+        (width as f32 / self.image.width as f32).min(height as f32 / self.image.height as f32)
+    }
+
+    /// Image pixel under the center of drawing pixel `(x, y)`.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<&Color> {
+        let image_x = ((x as isize - self.offset.0) as f32 + 0.5) / self.scale;
+        let image_y = ((y as isize - self.offset.1) as f32 + 0.5) / self.scale;
+        if image_x < 0. || image_y < 0. {
+            return None;
+        }
+        self.image.pixel(image_x as usize, image_y as usize)
+    }
+
+    /// Background `bg` with the image blended over it at drawing pixel `(x, y)`.
+    pub fn blend(&self, bg: [u8; 3], x: usize, y: usize) -> [u8; 3] {
+        let Some(color) = self.pixel(x, y) else {
+            return bg;
+        };
+        let mut color = color.clone();
+        color.a *= self.opacity;
+        color.to_rgb(bg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reference() {
+        let mut image = Drawing::new(2, 1);
+        image.pixels = vec![
+            Color::from_rgba8(255, 0, 0, 255),
+            Color::from_rgba8(0, 0, 255, 255),
+        ];
+        let mut reference = Reference::new(image);
+        reference.scale = 2.;
+        reference.offset = (1, 0);
+        assert_eq!(reference.pixel(0, 0), None);
+        assert_eq!(reference.pixel(2, 0).unwrap().to_rgba8(), [255, 0, 0, 255]);
+        assert_eq!(reference.pixel(3, 0).unwrap().to_rgba8(), [0, 0, 255, 255]);
+        assert_eq!(reference.pixel(5, 0), None);
+        assert_eq!(reference.blend([0, 0, 0], 1, 0), [128, 0, 0]);
+        assert_eq!(reference.blend([0, 0, 0], 0, 0), [0, 0, 0]);
+        assert_eq!(reference.fit(8, 2), 2.);
+    }
+}
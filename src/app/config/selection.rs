@@ -0,0 +1,99 @@
+use crate::drawing::MAX_SIZE;
+
+/// A rectangle region of the drawing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Selection {
+    /// Rectangle at `(x, y)`, `None` when empty or beyond the drawing size limit.
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Option<Self> {
+        let valid = (1..=MAX_SIZE).contains(&width)
+            && (1..=MAX_SIZE).contains(&height)
+            && x < MAX_SIZE
+            && y < MAX_SIZE;
+        valid.then_some(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// Rectangle spanned by two corner pixels, both included.
+    pub fn from_corners(a: (usize, usize), b: (usize, usize)) -> Self {
+        Self {
+            x: a.0.min(b.0),
+            y: a.1.min(b.1),
+            width: a.0.abs_diff(b.0) + 1,
+            height: a.1.abs_diff(b.1) + 1,
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        // the end past `usize::MAX` is not a bound
+        let within = |start: usize, len: usize, v: usize| {
+            v >= start && start.checked_add(len).is_none_or(|end| v < end)
+        };
+        within(self.x, self.width, x) && within(self.y, self.height, y)
+    }
+
+    /// Whether `(x, y)` is on the border of the rectangle.
+    pub fn on_edge(&self, x: usize, y: usize) -> bool {
+        let last = |start: usize, len: usize| start.checked_add(len.checked_sub(1)?);
+        self.contains(x, y)
+            && (x == self.x
+                || y == self.y
+                || last(self.x, self.width) == Some(x)
+                || last(self.y, self.height) == Some(y))
+    }
+
+    /// Pixels in the rectangle, row by row.
+    pub fn points(&self) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let (x, y, width, height) = (self.x, self.y, self.width, self.height);
+        (y..y.saturating_add(height))
+            .flat_map(move |y| (x..x.saturating_add(width)).map(move |x| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_corners() {
+        let selection = Selection::from_corners((3, 1), (1, 2));
+        assert_eq!(
+            selection,
+            Selection {
+                x: 1,
+                y: 1,
+                width: 3,
+                height: 2
+            }
+        );
+        assert_eq!(selection.points().count(), 6);
+        assert!(selection.on_edge(2, 1));
+        assert!(!selection.contains(0, 1));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let selection = Selection {
+            x: 2,
+            y: 0,
+            width: usize::MAX,
+            height: 1,
+        };
+        assert!(selection.contains(usize::MAX, 0));
+        assert!(!selection.on_edge(5, 1));
+        assert!(selection.on_edge(5, 0));
+        assert_eq!(Selection::new(0, 0, usize::MAX, 1), None);
+        assert_eq!(Selection::new(MAX_SIZE, 0, 1, 1), None);
+        assert!(Selection::new(0, 0, MAX_SIZE, 1).is_some());
+    }
+}
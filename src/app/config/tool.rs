@@ -0,0 +1,38 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+
+/// Tool used by mouse.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    /// Paint with the brush.
+    #[default]
+    Pencil,
+    /// Erase with the brush.
+    Eraser,
+    /// Fill a region with dithered gradient from primary to secondary color.
+    Gradient,
+}
+
+impl Tool {
+    pub fn label(self) -> &'static str {
+        match self {
+            Tool::Pencil => "pencil",
+            Tool::Eraser => "eraser",
+            Tool::Gradient => "gradient",
+        }
+    }
+}
+
+impl FromStr for Tool {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "pencil" => Tool::Pencil,
+            "eraser" => Tool::Eraser,
+            "gradient" => Tool::Gradient,
+            _ => bail!("unknown tool: {}", s),
+        })
+    }
+}
@@ -8,6 +8,17 @@ pub struct TransparencyGrid {
     pub light: [u8; 3],
 }
 
+impl TransparencyGrid {
+    /// Background color of pixel at `(x, y)`.
+    pub fn color_at(&self, x: usize, y: usize) -> [u8; 3] {
+        if (x / self.size + y / self.size).is_multiple_of(2) {
+            self.dark
+        } else {
+            self.light
+        }
+    }
+}
+
 // NOTE: the aseprite looking
 impl Default for TransparencyGrid {
     fn default() -> Self {
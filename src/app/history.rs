@@ -0,0 +1,64 @@
+use crate::drawing::Drawing;
+
+/// Snapshots of the drawing for undo and redo.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Drawing>,
+    redo: Vec<Drawing>,
+}
+
+impl History {
+    /// Number of undo steps kept.
+    const LIMIT: usize = 100;
+
+    /// Record the drawing before a change.
+    pub fn record(&mut self, drawing: &Drawing) {
+        if self.undo.len() == Self::LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(drawing.clone());
+        self.redo.clear();
+    }
+
+    /// Restore the last recorded drawing, return whether there was one.
+    pub fn undo(&mut self, drawing: &mut Drawing) -> bool {
+        let Some(previous) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(std::mem::replace(drawing, previous));
+        true
+    }
+
+    /// Restore the last undone drawing, return whether there was one.
+    pub fn redo(&mut self, drawing: &mut Drawing) -> bool {
+        let Some(next) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(std::mem::replace(drawing, next));
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::drawing::transform::Anchor;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::default();
+        let mut drawing = Drawing::new(1, 1);
+        history.record(&drawing);
+        drawing.resize(2, 2, Anchor::default());
+
+        assert!(history.undo(&mut drawing));
+        assert_eq!(drawing.width, 1);
+        assert!(!history.undo(&mut drawing));
+        assert!(history.redo(&mut drawing));
+        assert_eq!(drawing.width, 2);
+
+        history.undo(&mut drawing);
+        history.record(&drawing);
+        assert!(!history.redo(&mut drawing));
+    }
+}
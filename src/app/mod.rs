@@ -1,9 +1,12 @@
-use std::{cell::RefCell, path::PathBuf, pin::Pin, rc::Rc};
+use std::{cell::RefCell, io, path::PathBuf, pin::Pin, rc::Rc};
 
 use anyhow::Result;
 use crossterm::{
     self,
-    event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind},
+    cursor::MoveTo,
+    event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    execute,
+    style::Print,
     terminal::{WindowSize, window_size},
 };
 use csscolorparser::Color;
@@ -17,17 +20,48 @@ use tokio_stream::{Stream, StreamExt};
 
 pub mod action;
 pub mod config;
+pub mod history;
 pub mod runtime;
 
 use crate::{
     app::{
         action::Action,
-        config::{Config, mode::Mode},
+        config::{
+            Config,
+            brush::BrushShape,
+            mode::Mode,
+            pattern::Pattern,
+            picker::{Field, PickerState},
+            reference::Reference,
+            selection::Selection,
+            tool::Tool,
+        },
+        history::History,
         runtime::Runtime,
     },
-    drawing::{Drawing, io::load_drawing_from_file},
-    utils::mouse::{disable_mouse, enable_mouse},
-    widgets::{command_bar::CommandBar, status_bar::StatusBar, workspace::Workspace},
+    cli::Args,
+    drawing::{
+        Drawing, MAX_SIZE, aseprite,
+        dither::bayer,
+        format,
+        io::{
+            is_aseprite, load_drawing_from_file, load_drawing_or_image, load_image, load_palette,
+        },
+        line::{is_corner, line},
+        quantize::Method,
+        scale::{Filter, Size},
+        transform::{Anchor, Transform},
+    },
+    export::{self, gif::Animation},
+    graphics::Graphics,
+    utils::{
+        color_depth::ColorDepth,
+        mouse::{MouseMode, disable_mouse, enable_mouse},
+    },
+    widgets::{
+        canvas::Canvas, color_picker::ColorPicker, command_bar::CommandBar, status_bar::StatusBar,
+        workspace::Workspace,
+    },
 };
 
 #[derive(Debug)]
@@ -36,6 +70,45 @@ enum Event {
     Terminal(::crossterm::event::Event),
     /// Report error message.
     Message(String),
+    /// Loaded custom brush.
+    Brush(Drawing),
+    /// Loaded reference image.
+    Reference(Drawing),
+    /// Drawing written to path, with the number of changes made when it was written.
+    Written(PathBuf, usize),
+    /// Loaded palette to remap to, dithered when `true`.
+    Palette(Vec<[u8; 3]>, bool),
+}
+
+/// Order of the Bayer matrix dithering gradient.
+const GRADIENT_BAYER_ORDER: usize = 4;
+
+/// A continuous mouse painting.
+struct Stroke {
+    /// The drawing before the stroke.
+    original: Drawing,
+    /// Positions the stroke went through.
+    points: Vec<(usize, usize)>,
+    ink: Ink,
+}
+
+/// A gradient dragged by mouse.
+#[derive(Clone, Copy)]
+struct Gradient {
+    ink: Ink,
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
+/// What the mouse paints with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Ink {
+    /// Primary color, by left button.
+    Primary,
+    /// Secondary color, by right button.
+    Secondary,
+    /// Transparent, by eraser tool.
+    Erase,
 }
 
 pub struct App {
@@ -44,10 +117,27 @@ pub struct App {
     path: Option<PathBuf>,
     /// The data of actual drawing.
     drawing: Option<Drawing>,
+    /// Whether the drawing changed since last saved to `path`.
+    dirty: bool,
+    /// Number of changes made, to tell whether a finished write is still current.
+    edits: usize,
+    /// Whether `path` failed to load or loaded partially, so `:w` must not overwrite it.
+    readonly: bool,
+    history: History,
 
     // Retained areas.
     window_size: Option<WindowSize>,
     canvas_area: Option<Rect>,
+    /// Area of the mirror toggle in status bar.
+    mirror_toggle_area: Option<Rect>,
+    /// Area of color picker sliders.
+    picker_area: Option<Rect>,
+    /// Canvas position under the mouse.
+    hover: Option<(usize, usize)>,
+    /// Mouse stroke in progress.
+    stroke: Option<Stroke>,
+    /// Mouse gradient in progress.
+    gradient: Option<Gradient>,
 
     config: Rc<RefCell<Config>>,
 
@@ -58,12 +148,27 @@ pub struct App {
     stream: Pin<Box<dyn Stream<Item = Event>>>,
 
     message: Option<String>,
+
+    /// How the canvas is drawn.
+    graphics: Graphics,
+    /// The last image sequence written, to skip unchanged images.
+    graphics_drawn: Option<String>,
+    /// Cells covered by the last image, `None` when no image is shown.
+    graphics_size: Option<(u16, u16)>,
 }
 
 impl App {
     /// Create a new app.
-    pub fn new(path: Option<PathBuf>) -> Result<Self> {
-        let config = Rc::new(RefCell::new(Config::default()));
+    pub fn new(args: Args) -> Result<Self> {
+        let window_size = window_size().ok();
+        let config = Rc::new(RefCell::new(Config {
+            color_depth: args.colors.unwrap_or_else(ColorDepth::detect),
+            dither_colors: args.dither,
+            mouse_mode: args
+                .mouse
+                .unwrap_or_else(|| MouseMode::detect(window_size.as_ref())),
+            ..Default::default()
+        }));
         let runtime = RefCell::new(Runtime::new(config.clone()));
 
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
@@ -81,32 +186,66 @@ impl App {
         Ok(Self {
             drawing: None,
             should_exit: false,
-            path,
-            window_size: window_size().ok(),
+            path: args.path,
+            dirty: false,
+            edits: 0,
+            readonly: false,
+            history: History::default(),
+            window_size,
             canvas_area: None,
+            mirror_toggle_area: None,
+            picker_area: None,
+            hover: None,
+            stroke: None,
+            gradient: None,
             config,
             runtime,
             tx,
             message: None,
             stream,
+            graphics: args.graphics.unwrap_or_else(Graphics::detect),
+            graphics_drawn: None,
+            graphics_size: None,
         })
     }
 
     /// Run the app loop.
     pub async fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
         self.drawing = Some(match &self.path {
-            Some(path) => load_drawing_from_file(path).await.unwrap_or_default(),
+            Some(path) => match load_drawing_from_file(path).await {
+                Ok((drawing, warnings)) => {
+                    // saving a lossy import would drop what was not loaded
+                    if !warnings.is_empty() {
+                        self.readonly = true;
+                        self.message = Some(format!(
+                            "{}, opened read-only, :w! to overwrite",
+                            warnings.join(", ")
+                        ));
+                    }
+                    drawing
+                }
+                Err(error) if is_not_found(&error) => {
+                    self.message = Some(format!("new file {}", path.display()));
+                    Drawing::default()
+                }
+                Err(error) => {
+                    self.readonly = true;
+                    self.message = Some(format!(
+                        "failed to load {}: {:#}, opened read-only, :w! to overwrite",
+                        path.display(),
+                        error
+                    ));
+                    Drawing::default()
+                }
+            },
             None => Drawing::default(),
         });
-        // validate the drawing
-        if !self.drawing.as_mut().unwrap().validate() {
-            panic!("drawing is invliad");
-        }
 
-        enable_mouse()?;
+        enable_mouse(self.config.borrow().mouse_mode)?;
 
         while !self.should_exit {
             terminal.draw(|frame| self.render(frame))?;
+            self.draw_graphics(terminal)?;
             self.handle_event().await?;
         }
 
@@ -126,11 +265,38 @@ impl App {
         .split(frame.area());
 
         frame.render_stateful_widget(
-            Workspace::new(&self.config.borrow(), self.drawing.as_ref().unwrap()),
+            Workspace::new(
+                &self.config.borrow(),
+                self.drawing.as_ref().unwrap(),
+                self.preview_position(),
+            ),
             layout[0],
             &mut self.canvas_area,
         );
-        frame.render_widget(StatusBar::new(&self.config.borrow()), layout[1]);
+        let position = self.preview_position().map(|(x, y)| {
+            self.config
+                .borrow()
+                .wrap(self.drawing.as_ref().unwrap(), x, y)
+        });
+        frame.render_stateful_widget(
+            StatusBar::new(
+                &self.config.borrow(),
+                self.drawing.as_ref().unwrap(),
+                position,
+                self.path.as_deref(),
+                self.dirty,
+                self.readonly,
+            ),
+            layout[1],
+            &mut self.mirror_toggle_area,
+        );
+        if let Mode::Picker(_) = self.config.borrow().mode {
+            frame.render_stateful_widget(
+                ColorPicker::new(&self.config.borrow()),
+                layout[0],
+                &mut self.picker_area,
+            );
+        }
         let mut position = None;
         frame.render_stateful_widget(
             CommandBar::new(&self.config.borrow(), self.message.as_deref()),
@@ -144,6 +310,71 @@ impl App {
         }
     }
 
+    /// Where the brush preview is shown, which follows the keyboard cursor in cursor mode.
+    fn preview_position(&self) -> Option<(usize, usize)> {
+        let config = self.config.borrow();
+        match config.mode {
+            Mode::Cursor { .. } => Some(config.cursor),
+            _ => self.hover,
+        }
+    }
+
+    /// Draw the canvas as image over the half blocks, when a graphics protocol is used.
+    fn draw_graphics(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        let (Some(area), Some(window_size)) = (self.canvas_area, self.window_size.as_ref()) else {
+            return Ok(());
+        };
+        if self.graphics == Graphics::Halfblocks {
+            return Ok(());
+        }
+        let cell_width = (window_size.width / window_size.columns.max(1)).max(1);
+        let cell_height = (window_size.height / window_size.rows.max(1)).max(2);
+
+        let config = self.config.borrow();
+        // NOTE: the image would cover the dialog
+        let encoded = if let Mode::Picker(_) = config.mode {
+            None
+        } else {
+            // only the part of the canvas inside its area is shown
+            let drawing = self.drawing.as_ref().unwrap();
+            let (width, height) = config.view_size(drawing);
+            let columns = width.min(area.width as usize);
+            let rows = height.div_ceil(2).min(area.height as usize);
+            let canvas = Canvas::new(&config, drawing, self.preview_position());
+            let mut image = canvas.image(columns, rows * 2);
+            // kitty and iTerm2 size the image by cells, sixel is drawn pixel by pixel,
+            // so each drawing pixel is scaled to the size of half cell
+            if self.graphics == Graphics::Sixel {
+                image = image.scale(cell_width as usize, cell_height as usize / 2);
+            }
+            let size = (columns as u16, rows as u16);
+            self.graphics
+                .encode(&image, size.0, size.1)?
+                .map(|sequence| (sequence, size))
+        };
+        drop(config);
+
+        // cells under a shrunk or removed image are redrawn
+        let size = encoded.as_ref().map(|(_, size)| *size);
+        if self.graphics_size.is_some() && size != self.graphics_size {
+            if let Some(clear) = self.graphics.clear() {
+                execute!(io::stdout(), Print(clear))?;
+            }
+            terminal.clear()?;
+            terminal.draw(|frame| self.render(frame))?;
+            self.graphics_drawn = None;
+        }
+        self.graphics_size = size;
+
+        if let Some((sequence, _)) = encoded
+            && self.graphics_drawn.as_ref() != Some(&sequence)
+        {
+            execute!(io::stdout(), MoveTo(area.x, area.y), Print(&sequence))?;
+            self.graphics_drawn = Some(sequence);
+        }
+        Ok(())
+    }
+
     async fn handle_event(&mut self) -> Result<()> {
         // reset message
         self.message = None;
@@ -153,13 +384,28 @@ impl App {
                 Event::Terminal(event) => {
                     match event {
                         crossterm::event::Event::Key(key) => self.on_key(key)?,
-                        crossterm::event::Event::Mouse(mouse) => self.on_mouse(mouse),
+                        crossterm::event::Event::Mouse(mouse) => self.on_mouse(mouse)?,
                         // NOTE: we need both cell size and pixel size, so the resize event fields is not used.
                         crossterm::event::Event::Resize(_, _) => self.on_resize(),
                         _ => {}
                     }
                 }
                 Event::Message(message) => self.message = Some(message),
+                Event::Brush(brush) => {
+                    self.perform(Action::SetBrushShape(BrushShape::Custom(brush)))?
+                }
+                Event::Reference(image) => {
+                    self.config.borrow_mut().reference = Some(Reference::new(image))
+                }
+                Event::Written(path, edits) => {
+                    if Some(&path) == self.path.as_ref() {
+                        self.readonly = false;
+                        // changes made while writing are still unsaved
+                        self.dirty = edits != self.edits;
+                    }
+                    self.message = Some("write success".to_string());
+                }
+                Event::Palette(palette, dither) => self.perform(Action::Remap(palette, dither))?,
             }
         }
 
@@ -173,16 +419,31 @@ impl App {
             Mode::Normal => match key.code {
                 KeyCode::Char(':') => Action::EnterCommandMode,
                 KeyCode::Char('+') | KeyCode::Char('=') => {
-                    Action::Resize(drawing.width + 1, drawing.height + 1)
+                    Action::Resize(drawing.width + 1, drawing.height + 1, Anchor::default())
                 }
-                KeyCode::Char('-') => {
-                    if drawing.width > 1 {
-                        Action::Resize(drawing.width - 1, drawing.height - 1)
-                    } else {
-                        return Ok(());
-                    }
+                KeyCode::Char('-') if drawing.width > 1 && drawing.height > 1 => {
+                    Action::Resize(drawing.width - 1, drawing.height - 1, Anchor::default())
                 }
                 KeyCode::Char('E') => Action::Erase,
+                KeyCode::Char('b') => Action::SetTool(Tool::Pencil),
+                KeyCode::Char('g') => Action::SetTool(Tool::Gradient),
+                KeyCode::Char('e') => Action::SetTool(Tool::Eraser),
+                KeyCode::Char('x') => Action::SwapColors,
+                KeyCode::Char('c') => Action::EnterPickerMode,
+                KeyCode::Char('i') => Action::EnterCursorMode,
+                KeyCode::Char('#') => Action::Grid(None),
+                KeyCode::Char('t') => Action::Tile(None),
+                KeyCode::Char('u') => Action::Undo,
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Redo,
+                KeyCode::Char('f') => Action::Transform(Transform::FlipHorizontal),
+                KeyCode::Char('F') => Action::Transform(Transform::FlipVertical),
+                KeyCode::Char('r') => Action::Transform(Transform::Rotate(1)),
+                KeyCode::Char('R') => Action::Transform(Transform::Rotate(3)),
+                KeyCode::Char('`') => Action::LowerHalf(None),
+                KeyCode::Char('[') => {
+                    Action::SetBrushSize(self.config.borrow().brush.size().saturating_sub(1))
+                }
+                KeyCode::Char(']') => Action::SetBrushSize(self.config.borrow().brush.size() + 1),
                 KeyCode::Char(ch @ '1')
                 | KeyCode::Char(ch @ '2')
                 | KeyCode::Char(ch @ '3')
@@ -204,8 +465,64 @@ impl App {
                 KeyCode::Enter => Action::Execute(command.clone()),
                 _ => return Ok(()),
             },
+            Mode::Picker(_) => {
+                let steps = if key.modifiers.contains(KeyModifiers::SHIFT) {
+                    10
+                } else {
+                    1
+                };
+                match key.code {
+                    KeyCode::Esc => Action::EnterNormalMode,
+                    KeyCode::Enter => Action::PickerCommit,
+                    KeyCode::Up | KeyCode::BackTab => Action::PickerFocus(-1),
+                    KeyCode::Down | KeyCode::Tab => Action::PickerFocus(1),
+                    KeyCode::Left => Action::PickerAdjust(-steps),
+                    KeyCode::Right => Action::PickerAdjust(steps),
+                    KeyCode::Char(ch) => Action::PickerInput(ch),
+                    KeyCode::Backspace => Action::PickerErase,
+                    _ => return Ok(()),
+                }
+            }
+            Mode::Cursor { count, anchor } => {
+                let counting = count.is_some();
+                let count = count.unwrap_or(1) as isize;
+                match key.code {
+                    KeyCode::Esc if anchor.is_some() => Action::Select(None),
+                    KeyCode::Esc => Action::EnterNormalMode,
+                    KeyCode::Char(':') => Action::EnterCommandMode,
+                    KeyCode::Char(ch) if ch.is_ascii_digit() && (ch != '0' || counting) => {
+                        Action::CursorCount(ch.to_digit(10).unwrap() as usize)
+                    }
+                    KeyCode::Char('h') | KeyCode::Left => Action::MoveCursor(-count, 0),
+                    KeyCode::Char('j') | KeyCode::Down => Action::MoveCursor(0, count),
+                    KeyCode::Char('k') | KeyCode::Up => Action::MoveCursor(0, -count),
+                    KeyCode::Char('l') | KeyCode::Right => Action::MoveCursor(count, 0),
+                    KeyCode::Char(' ') => Action::PaintCursor,
+                    KeyCode::Char('x') => Action::EraseCursor,
+                    KeyCode::Char('v') => Action::ToggleVisual,
+                    KeyCode::Char('u') => Action::Undo,
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        Action::Redo
+                    }
+                    KeyCode::Char('y') => match self.config.borrow().selection {
+                        Some(selection) => Action::CaptureBrush(
+                            selection.x,
+                            selection.y,
+                            selection.width,
+                            selection.height,
+                        ),
+                        None => return Ok(()),
+                    },
+                    _ => return Ok(()),
+                }
+            }
         };
+        let counting = matches!(action, Action::CursorCount(_));
         self.perform(action)?;
+        // count applies to the next key only
+        if !counting && let Mode::Cursor { count, .. } = &mut self.config.borrow_mut().mode {
+            *count = None;
+        }
         Ok(())
     }
 
@@ -214,36 +531,121 @@ impl App {
         // as execute_script also borrow mutably.
         match action {
             Action::Quit => self.should_exit = true,
-            Action::Save(path) => {
-                self.write(path)?;
+            Action::Save(path, force) => {
+                self.write(path, force)?;
             }
+            Action::Export(path) => self.export(path)?,
             Action::EnterCommandMode => {
                 self.config.borrow_mut().mode = Mode::Command(String::new())
             }
             Action::EnterNormalMode => self.config.borrow_mut().mode = Mode::Normal,
             Action::CommandPush(ch) => match &mut self.config.borrow_mut().mode {
-                Mode::Normal => self
+                Mode::Command(command) => command.push(ch),
+                _ => self
                     .tx
                     .send(Event::Message("Not command mode".to_string()))?,
-                Mode::Command(command) => command.push(ch),
             },
             Action::CommandPop => match &mut self.config.borrow_mut().mode {
-                Mode::Normal => self
-                    .tx
-                    .send(Event::Message("Not command mode".to_string()))?,
                 Mode::Command(command) => {
                     command.pop();
                 }
+                _ => self
+                    .tx
+                    .send(Event::Message("Not command mode".to_string()))?,
             },
-            Action::Resize(w, h) => {
+            Action::EnterPickerMode => {
+                let mut config = self.config.borrow_mut();
+                config.mode = Mode::Picker(PickerState::new(&config.color));
+            }
+            Action::PickerFocus(delta) => self.update_picker(|picker| picker.move_focus(delta))?,
+            Action::PickerAdjust(steps) => self.update_picker(|picker| picker.adjust(steps))?,
+            Action::PickerSet(field, fraction) => {
+                self.update_picker(|picker| picker.set_fraction(field, fraction))?
+            }
+            Action::PickerInput(ch) => self.update_picker(|picker| picker.input(ch))?,
+            Action::PickerErase => self.update_picker(|picker| picker.erase())?,
+            Action::PickerCommit => {
+                let mut config = self.config.borrow_mut();
+                if let Mode::Picker(picker) = &config.mode {
+                    let color = picker.color();
+                    config.set_color(color);
+                }
+                config.mode = Mode::Normal;
+            }
+            Action::EnterCursorMode => {
+                let drawing = self.drawing.as_ref().unwrap();
+                let mut config = self.config.borrow_mut();
+                config.cursor = (
+                    config.cursor.0.min(drawing.width - 1),
+                    config.cursor.1.min(drawing.height - 1),
+                );
+                config.mode = Mode::Cursor {
+                    count: None,
+                    anchor: None,
+                };
+            }
+            Action::CursorCount(digit) => {
+                if let Mode::Cursor { count, .. } = &mut self.config.borrow_mut().mode {
+                    *count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                }
+            }
+            Action::MoveCursor(dx, dy) => {
+                let drawing = self.drawing.as_ref().unwrap();
+                let mut config = self.config.borrow_mut();
+                let move_by = |v: usize, d: isize, len: usize| {
+                    v.saturating_add_signed(d).min(len.saturating_sub(1))
+                };
+                let cursor = (
+                    move_by(config.cursor.0, dx, drawing.width),
+                    move_by(config.cursor.1, dy, drawing.height),
+                );
+                config.cursor = cursor;
+                if let Mode::Cursor {
+                    anchor: Some(anchor),
+                    ..
+                } = config.mode
+                {
+                    config.selection = Some(Selection::from_corners(anchor, cursor));
+                }
+            }
+            Action::PaintCursor => self.paint_cursor(Ink::Primary),
+            Action::EraseCursor => self.paint_cursor(Ink::Erase),
+            Action::ToggleVisual => {
+                let mut config = self.config.borrow_mut();
+                let cursor = config.cursor;
+                if let Mode::Cursor { anchor, .. } = &mut config.mode
+                    && anchor.take().is_none()
+                {
+                    *anchor = Some(cursor);
+                    config.selection = Some(Selection::from_corners(cursor, cursor));
+                }
+            }
+            Action::Select(selection) => {
+                let mut config = self.config.borrow_mut();
+                config.selection = selection;
+                if let Mode::Cursor { anchor, .. } = &mut config.mode {
+                    *anchor = None;
+                }
+            }
+            Action::Resize(w, h, _)
+                if !(1..=MAX_SIZE).contains(&w) || !(1..=MAX_SIZE).contains(&h) =>
+            {
+                self.tx.send(Event::Message(format!(
+                    "size must be between 1x1 and {}x{}",
+                    MAX_SIZE, MAX_SIZE
+                )))?
+            }
+            Action::Resize(w, h, anchor) => {
+                self.checkpoint();
                 if let Some(drawing) = self.drawing.as_mut() {
-                    drawing.resize(w, h);
+                    drawing.resize(w, h, anchor);
                 } else {
                     self.tx
                         .send(Event::Message("drawing is None".to_string()))?
                 }
             }
             Action::Erase => {
+                self.checkpoint();
                 if let Some(drawing) = self.drawing.as_mut() {
                     drawing.erase_all();
                 } else {
@@ -251,6 +653,23 @@ impl App {
                         .send(Event::Message("drawing is None".to_string()))?
                 }
             }
+            Action::Undo => {
+                if self.history.undo(self.drawing.as_mut().unwrap()) {
+                    self.changed();
+                } else {
+                    self.tx
+                        .send(Event::Message("Already at oldest change".to_string()))?;
+                }
+            }
+            Action::Redo => {
+                if self.history.redo(self.drawing.as_mut().unwrap()) {
+                    self.changed();
+                } else {
+                    self.tx
+                        .send(Event::Message("Already at newest change".to_string()))?;
+                }
+            }
+            Action::Transform(transform) => self.transform(transform),
             Action::GetColor => {
                 let color = self.config.borrow().color.to_css_hex();
                 self.tx
@@ -272,6 +691,136 @@ impl App {
                     }
                 }
             },
+            Action::SecondaryColor(color) => match color {
+                Some(color) => self.config.borrow_mut().secondary_color = color,
+                None => {
+                    let color = self.config.borrow().secondary_color.to_css_hex();
+                    self.tx
+                        .send(Event::Message(format!("Secondary color: {}", color)))?;
+                }
+            },
+            Action::SwapColors => self.config.borrow_mut().swap_colors(),
+            Action::SetTool(tool) => self.config.borrow_mut().tool = tool,
+            Action::SetPattern(pattern) => self.config.borrow_mut().pattern = pattern,
+            Action::Blend(mode) => match mode {
+                Some(mode) => self.config.borrow_mut().blend = mode,
+                None => {
+                    let mode = self.config.borrow().blend;
+                    self.tx
+                        .send(Event::Message(format!("Blend: {}", mode.label())))?;
+                }
+            },
+            Action::GetBrush => {
+                let brush = self.config.borrow().brush.label();
+                self.tx
+                    .send(Event::Message(format!("Current brush: {}", brush)))?;
+            }
+            Action::SetBrushShape(shape) => {
+                let result = self.config.borrow_mut().brush.set_shape(shape);
+                if let Err(error) = result {
+                    self.tx.send(Event::Message(error.to_string()))?
+                }
+            }
+            Action::SetBrushSize(size) => self.config.borrow_mut().brush.set_size(size),
+            Action::CaptureBrush(x, y, width, height) => {
+                let brush = self.drawing.as_ref().unwrap().crop(x, y, width, height);
+                self.perform(Action::SetBrushShape(BrushShape::Custom(brush)))?
+            }
+            Action::LoadBrush(path) => {
+                let tx = self.tx.clone();
+                tokio::spawn(async move {
+                    let event = match load_drawing_or_image(&path).await {
+                        Ok(brush) => Event::Brush(brush),
+                        Err(error) => Event::Message(error.to_string()),
+                    };
+                    tx.send(event).unwrap();
+                });
+            }
+            Action::PixelPerfect(enabled) => {
+                let mut config = self.config.borrow_mut();
+                config.pixel_perfect = enabled.unwrap_or(!config.pixel_perfect);
+            }
+            Action::LowerHalf(lower) => {
+                let mut config = self.config.borrow_mut();
+                config.lower_half = lower.unwrap_or(!config.lower_half);
+            }
+            Action::Mirror(mode) => match mode {
+                Some(mode) => self.config.borrow_mut().mirror.mode = mode,
+                None => {
+                    let mode = self.config.borrow().mirror.mode;
+                    self.tx
+                        .send(Event::Message(format!("Mirror: {}", mode.label())))?;
+                }
+            },
+            Action::MirrorAxis(axis) => self.config.borrow_mut().mirror.axis = axis,
+            Action::Tile(enabled) => {
+                let mut config = self.config.borrow_mut();
+                config.tile = enabled.unwrap_or(!config.tile);
+            }
+            Action::Grid(enabled) => {
+                let mut config = self.config.borrow_mut();
+                config.grid.enabled = enabled.unwrap_or(!config.grid.enabled);
+            }
+            Action::GridSpacing(spacing) => {
+                let mut config = self.config.borrow_mut();
+                config.grid.spacing = spacing.max(1);
+                config.grid.enabled = true;
+            }
+            Action::GridColor(color) => {
+                let [r, g, b, _] = color.to_rgba8();
+                self.config.borrow_mut().grid.color = [r, g, b];
+            }
+            Action::GuideColumn(x) => self.config.borrow_mut().grid.toggle_column(x),
+            Action::GuideRow(y) => self.config.borrow_mut().grid.toggle_row(y),
+            Action::ClearGuides => {
+                let mut config = self.config.borrow_mut();
+                config.grid.columns.clear();
+                config.grid.rows.clear();
+            }
+            Action::LoadReference(path) => {
+                let tx = self.tx.clone();
+                tokio::spawn(async move {
+                    let event = match load_image(&path).await {
+                        Ok(image) => Event::Reference(image),
+                        Err(error) => Event::Message(error.to_string()),
+                    };
+                    tx.send(event).unwrap();
+                });
+            }
+            Action::ReferenceOpacity(opacity) => {
+                if let Some(reference) = &mut self.config.borrow_mut().reference {
+                    reference.opacity = opacity;
+                }
+            }
+            Action::ReferenceScale(scale) => {
+                let drawing = self.drawing.as_ref().unwrap();
+                if let Some(reference) = &mut self.config.borrow_mut().reference {
+                    reference.scale =
+                        scale.unwrap_or_else(|| reference.fit(drawing.width, drawing.height));
+                }
+            }
+            Action::ReferenceOffset(x, y) => {
+                if let Some(reference) = &mut self.config.borrow_mut().reference {
+                    reference.offset = (x, y);
+                }
+            }
+            Action::ClearReference => self.config.borrow_mut().reference = None,
+            Action::Quantize(max, method) => {
+                self.recolor(|drawing| drawing.quantize(max, method))?
+            }
+            Action::Remap(palette, dither) => {
+                self.recolor(|drawing| drawing.remap(&palette, dither))?
+            }
+            Action::LoadPalette(path, dither) => {
+                let tx = self.tx.clone();
+                tokio::spawn(async move {
+                    let event = match load_palette(&path).await {
+                        Ok(palette) => Event::Palette(palette, dither),
+                        Err(error) => Event::Message(error.to_string()),
+                    };
+                    tx.send(event).unwrap();
+                });
+            }
             Action::Execute(command) => {
                 match command.strip_prefix('=') {
                     Some(script) => {
@@ -281,47 +830,314 @@ impl App {
                         self.run_command(&command)?;
                     }
                 }
-                self.config.borrow_mut().mode = Mode::Normal;
+                let mut config = self.config.borrow_mut();
+                config.mode = std::mem::take(&mut config.mode).after_execute(&command);
             }
         }
 
         Ok(())
     }
 
+    /// Update color picker state, only in picker mode.
+    fn update_picker(&self, f: impl FnOnce(&mut PickerState)) -> Result<()> {
+        match &mut self.config.borrow_mut().mode {
+            Mode::Picker(picker) => f(picker),
+            _ => self
+                .tx
+                .send(Event::Message("Not picker mode".to_string()))?,
+        }
+        Ok(())
+    }
+
     fn run_command(&mut self, command: &str) -> Result<()> {
         // TODO: should use something like shlex or vim syntax parser
         let command = command.split_ascii_whitespace().collect_vec();
         match command.as_slice() {
-            ["w"] => self.perform(Action::Save(None))?,
-            ["w", path] => self.perform(Action::Save(Some(PathBuf::from(path))))?,
+            ["w"] => self.perform(Action::Save(None, false))?,
+            ["w", path] => self.perform(Action::Save(Some(PathBuf::from(path)), false))?,
+            ["w!"] => self.perform(Action::Save(None, true))?,
+            ["w!", path] => self.perform(Action::Save(Some(PathBuf::from(path)), true))?,
+            ["export", path] => self.perform(Action::Export(PathBuf::from(path)))?,
             ["q"] => {
                 self.perform(Action::Quit)?;
             }
             ["color"] => {
                 self.perform(Action::GetColor)?;
             }
+            ["picker"] => self.perform(Action::EnterPickerMode)?,
             ["color", arg] => match csscolorparser::parse(arg) {
                 Ok(color) => {
                     self.perform(Action::SetColor(either::Either::Left(color)))?;
                 }
                 Err(error) => self.tx.send(Event::Message(error.to_string()))?,
             },
+            ["secondary"] => self.perform(Action::SecondaryColor(None))?,
+            ["secondary", arg] => match csscolorparser::parse(arg) {
+                Ok(color) => self.perform(Action::SecondaryColor(Some(color)))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["tool", tool] => match tool.parse() {
+                Ok(tool) => self.perform(Action::SetTool(tool))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["pattern", name, rest @ ..] if rest.len() <= 1 => {
+                match Pattern::parse(name, rest.first().copied()) {
+                    Ok(pattern) => self.perform(Action::SetPattern(pattern))?,
+                    Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+                }
+            }
+            ["blend"] => self.perform(Action::Blend(None))?,
+            ["blend", mode] => match mode.parse() {
+                Ok(mode) => self.perform(Action::Blend(Some(mode)))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["brush"] => self.perform(Action::GetBrush)?,
+            ["brush", "capture", x, y, width, height] => {
+                match (x.parse(), y.parse(), width.parse(), height.parse()) {
+                    (Ok(x), Ok(y), Ok(width), Ok(height))
+                        if let Some(region) = Selection::new(x, y, width, height) =>
+                    {
+                        self.perform(Action::CaptureBrush(
+                            region.x,
+                            region.y,
+                            region.width,
+                            region.height,
+                        ))?
+                    }
+                    _ => self
+                        .tx
+                        .send(Event::Message("invalid brush region".to_string()))?,
+                }
+            }
+            ["brush", "load", path] => self.perform(Action::LoadBrush(PathBuf::from(path)))?,
+            ["brush", shape, rest @ ..] if matches!(*shape, "square" | "round") => {
+                let shape = match *shape {
+                    "round" => BrushShape::Round,
+                    _ => BrushShape::Square,
+                };
+                self.perform(Action::SetBrushShape(shape))?;
+                if let [size] = rest {
+                    self.run_command(&format!("brush {}", size))?;
+                }
+            }
+            ["brush", size] => match size.parse() {
+                Ok(size) => self.perform(Action::SetBrushSize(size))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["pixelperfect"] => self.perform(Action::PixelPerfect(None))?,
+            ["pixelperfect", "on"] => self.perform(Action::PixelPerfect(Some(true)))?,
+            ["pixelperfect", "off"] => self.perform(Action::PixelPerfect(Some(false)))?,
+            ["half", "top"] => self.perform(Action::LowerHalf(Some(false)))?,
+            ["half", "bottom"] => self.perform(Action::LowerHalf(Some(true)))?,
+            ["canvas"] => {
+                // prefill the command line with the current size to edit
+                let drawing = self.drawing.as_ref().unwrap();
+                let command = format!("resize {}x{} center", drawing.width, drawing.height);
+                self.config.borrow_mut().mode = Mode::Command(command);
+            }
+            ["resize", size, rest @ ..] if rest.len() <= 1 => {
+                let size = size
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+                let anchor = rest.first().map_or(Ok(Anchor::default()), |a| a.parse());
+                match (size, anchor) {
+                    (Some((width, height)), Ok(anchor)) => {
+                        self.perform(Action::Resize(width, height, anchor))?
+                    }
+                    (None, _) => self
+                        .tx
+                        .send(Event::Message("size should be like 32x16".to_string()))?,
+                    (_, Err(error)) => self.tx.send(Event::Message(error.to_string()))?,
+                }
+            }
+            ["undo"] => self.perform(Action::Undo)?,
+            ["redo"] => self.perform(Action::Redo)?,
+            ["flip", axis] => match Transform::flip(axis) {
+                Ok(transform) => self.perform(Action::Transform(transform))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["rotate", degrees] => match Transform::rotate(degrees) {
+                Ok(transform) => self.perform(Action::Transform(transform))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["shift", dx, dy] => match (dx.parse(), dy.parse()) {
+                (Ok(dx), Ok(dy)) => self.perform(Action::Transform(Transform::Shift(dx, dy)))?,
+                _ => self
+                    .tx
+                    .send(Event::Message("invalid shift offset".to_string()))?,
+            },
+            ["scale", size, rest @ ..] if rest.len() <= 1 => {
+                let filter = rest.first().map_or(Ok(Filter::default()), |f| f.parse());
+                match (size.parse::<Size>(), filter) {
+                    (Ok(size), Ok(filter)) => {
+                        let (width, height) = match self.config.borrow().selection {
+                            Some(selection) => (selection.width, selection.height),
+                            None => {
+                                let drawing = self.drawing.as_ref().unwrap();
+                                (drawing.width, drawing.height)
+                            }
+                        };
+                        match size.resolve(width, height) {
+                            Ok((width, height)) => self.perform(Action::Transform(
+                                Transform::Scale(width, height, filter),
+                            ))?,
+                            Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+                        }
+                    }
+                    (Err(error), _) | (_, Err(error)) => {
+                        self.tx.send(Event::Message(error.to_string()))?
+                    }
+                }
+            }
+            ["tile"] => self.perform(Action::Tile(None))?,
+            ["tile", "on"] => self.perform(Action::Tile(Some(true)))?,
+            ["tile", "off"] => self.perform(Action::Tile(Some(false)))?,
+            ["grid"] => self.perform(Action::Grid(None))?,
+            ["grid", "on"] => self.perform(Action::Grid(Some(true)))?,
+            ["grid", "off"] => self.perform(Action::Grid(Some(false)))?,
+            ["grid", "color", arg] => match csscolorparser::parse(arg) {
+                Ok(color) => self.perform(Action::GridColor(color))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["grid", spacing] => match spacing.parse() {
+                Ok(spacing) if spacing > 0 => self.perform(Action::GridSpacing(spacing))?,
+                _ => self
+                    .tx
+                    .send(Event::Message("invalid grid spacing".to_string()))?,
+            },
+            ["guide", "clear"] => self.perform(Action::ClearGuides)?,
+            ["guide", axis @ ("x" | "y"), position] => match (*axis, position.parse()) {
+                ("x", Ok(x)) => self.perform(Action::GuideColumn(x))?,
+                (_, Ok(y)) => self.perform(Action::GuideRow(y))?,
+                (_, Err(error)) => self.tx.send(Event::Message(error.to_string()))?,
+            },
+            ["quantize", max, rest @ ..] if rest.len() <= 1 => {
+                let method = rest.first().map_or(Ok(Method::default()), |m| m.parse());
+                match (max.parse(), method) {
+                    (Ok(max), Ok(method)) if max > 0 => {
+                        self.perform(Action::Quantize(max, method))?
+                    }
+                    (_, Err(error)) => self.tx.send(Event::Message(error.to_string()))?,
+                    _ => self
+                        .tx
+                        .send(Event::Message("invalid number of colors".to_string()))?,
+                }
+            }
+            ["remap", palette, rest @ ..] if matches!(rest, [] | ["dither"]) => {
+                let dither = !rest.is_empty();
+                let colors = palette
+                    .split(',')
+                    .map(csscolorparser::parse)
+                    .collect::<Result<Vec<_>, _>>();
+                match colors {
+                    Ok(colors) => {
+                        let palette = colors
+                            .iter()
+                            .map(|color| {
+                                let [r, g, b, _] = color.to_rgba8();
+                                [r, g, b]
+                            })
+                            .collect();
+                        self.perform(Action::Remap(palette, dither))?
+                    }
+                    Err(_) => self.perform(Action::LoadPalette(PathBuf::from(palette), dither))?,
+                }
+            }
+            ["reference", "off"] => self.perform(Action::ClearReference)?,
+            ["reference", "opacity", opacity] => match opacity.parse() {
+                Ok(opacity) if (0. ..=1.).contains(&opacity) => {
+                    self.perform(Action::ReferenceOpacity(opacity))?
+                }
+                _ => self.tx.send(Event::Message(
+                    "opacity must be between 0 and 1".to_string(),
+                ))?,
+            },
+            ["reference", "scale", "fit"] => self.perform(Action::ReferenceScale(None))?,
+            ["reference", "scale", scale] => match scale.parse() {
+                Ok(scale) if scale > 0. => self.perform(Action::ReferenceScale(Some(scale)))?,
+                _ => self
+                    .tx
+                    .send(Event::Message("invalid reference scale".to_string()))?,
+            },
+            ["reference", "offset", x, y] => match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => self.perform(Action::ReferenceOffset(x, y))?,
+                _ => self
+                    .tx
+                    .send(Event::Message("invalid reference offset".to_string()))?,
+            },
+            ["reference", path] => self.perform(Action::LoadReference(PathBuf::from(path)))?,
+            ["select", "none"] => self.perform(Action::Select(None))?,
+            ["select", "all"] => {
+                let drawing = self.drawing.as_ref().unwrap();
+                let selection = Selection {
+                    x: 0,
+                    y: 0,
+                    width: drawing.width,
+                    height: drawing.height,
+                };
+                self.perform(Action::Select(Some(selection)))?
+            }
+            ["select", x, y, width, height] => {
+                match (x.parse(), y.parse(), width.parse(), height.parse()) {
+                    (Ok(x), Ok(y), Ok(width), Ok(height))
+                        if let Some(selection) = Selection::new(x, y, width, height) =>
+                    {
+                        self.perform(Action::Select(Some(selection)))?
+                    }
+                    _ => self
+                        .tx
+                        .send(Event::Message("invalid selection".to_string()))?,
+                }
+            }
+            ["mirror"] => self.perform(Action::Mirror(None))?,
+            ["mirror", "axis", "center"] => self.perform(Action::MirrorAxis(None))?,
+            ["mirror", "axis", x, y] => match (x.parse::<f32>(), y.parse::<f32>()) {
+                (Ok(x), Ok(y)) if x >= 0. && y >= 0. => {
+                    let axis = ((x * 2.).round() as usize, (y * 2.).round() as usize);
+                    self.perform(Action::MirrorAxis(Some(axis)))?;
+                }
+                _ => self
+                    .tx
+                    .send(Event::Message("invalid mirror axis".to_string()))?,
+            },
+            ["mirror", mode] => match mode.parse() {
+                Ok(mode) => self.perform(Action::Mirror(Some(mode)))?,
+                Err(error) => self.tx.send(Event::Message(error.to_string()))?,
+            },
             _ => {}
         }
         Ok(())
     }
 
-    fn write(&self, path: Option<PathBuf>) -> Result<()> {
+    /// Write the drawing to `path` or the opened path, `force` to overwrite a file failed to load.
+    fn write(&mut self, path: Option<PathBuf>, force: bool) -> Result<()> {
         let tx = self.tx.clone();
-        // TODO: make drawing arc
-        let serialized = serde_json::to_string(&self.drawing)?;
         if let Some(path) = path.or(self.path.to_owned()) {
+            let opened = Some(&path) == self.path.as_ref();
+            if opened && self.readonly && !force {
+                self.tx.send(Event::Message(
+                    "file was not fully loaded, use :w! to overwrite".to_string(),
+                ))?;
+                return Ok(());
+            }
+            // TODO: make drawing arc
+            let drawing = self.drawing.as_ref().unwrap();
+            let serialized = if is_aseprite(&path) {
+                aseprite::encode(std::slice::from_ref(drawing), &[100])?
+            } else {
+                format::encode(drawing)?.into_bytes()
+            };
             // TODO: make path arc
             let path = path.to_path_buf();
+            let edits = self.edits;
             tokio::spawn(async move {
-                tokio::fs::write(path, serialized).await.unwrap();
-                tx.send(Event::Message("write success".to_string()))
-                    .unwrap();
+                let event = match tokio::fs::write(&path, serialized).await {
+                    Ok(()) => Event::Written(path, edits),
+                    Err(error) => {
+                        Event::Message(format!("failed to write {}: {}", path.display(), error))
+                    }
+                };
+                tx.send(event).unwrap();
             });
         } else {
             self.tx
@@ -330,55 +1146,386 @@ impl App {
         Ok(())
     }
 
+    fn export(&self, path: PathBuf) -> Result<()> {
+        let drawing = self.drawing.as_ref().unwrap();
+        let (data, note) =
+            match export::encode(std::slice::from_ref(drawing), &path, &Animation::default()) {
+                Ok(encoded) => encoded,
+                Err(error) => {
+                    self.tx.send(Event::Message(error.to_string()))?;
+                    return Ok(());
+                }
+            };
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let message = match tokio::fs::write(&path, data).await {
+                Ok(()) => match note {
+                    Some(note) => format!("exported, {}", note),
+                    None => "export success".to_string(),
+                },
+                Err(error) => error.to_string(),
+            };
+            tx.send(Event::Message(message)).unwrap();
+        });
+        Ok(())
+    }
+
     /// Handle mouse event.
-    fn on_mouse(&mut self, mouse: MouseEvent) {
-        if let Some((px, py)) = self.viewport_to_canvas(mouse.column, mouse.row) {
-            if let Some(pixel) = self
-                .drawing
-                .as_mut()
-                .unwrap()
-                .pixel_mut(px as usize, py as usize)
+    fn on_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
+        let picking = matches!(self.config.borrow().mode, Mode::Picker(_));
+        if picking {
+            if let MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) =
+                mouse.kind
+                && let Some((x, y)) = self.viewport_to_cell(mouse.column, mouse.row)
+                && let Some(area) = self.picker_area
+                && area.contains((x, y).into())
             {
-                match mouse.kind {
-                    MouseEventKind::Down(mouse_button) | MouseEventKind::Drag(mouse_button) => {
-                        match mouse_button {
-                            MouseButton::Left => {
-                                *pixel = self.config.borrow().color.clone();
-                            }
-                            MouseButton::Right => {
-                                *pixel = Color::from_rgba8(0, 0, 0, 0);
-                            }
-                            _ => {}
+                let field = Field::ALL[(y - area.y) as usize];
+                let fraction = (x - area.x) as f32 / (area.width - 1).max(1) as f32;
+                self.perform(Action::PickerSet(field, fraction))?;
+            }
+            return Ok(());
+        }
+
+        if let MouseEventKind::Down(MouseButton::Left) = mouse.kind
+            && let Some(position) = self.viewport_to_cell(mouse.column, mouse.row)
+            && self
+                .mirror_toggle_area
+                .is_some_and(|area| area.contains(position.into()))
+        {
+            let mut config = self.config.borrow_mut();
+            config.mirror.mode = config.mirror.mode.next();
+            return Ok(());
+        }
+
+        let (width, height) = self
+            .config
+            .borrow()
+            .view_size(self.drawing.as_ref().unwrap());
+        self.hover = self
+            .viewport_to_canvas(mouse.column, mouse.row, mouse.modifiers)
+            .map(|(x, y)| (x as usize, y as usize))
+            .filter(|&(x, y)| x < width && y < height);
+
+        match mouse.kind {
+            MouseEventKind::Down(mouse_button) | MouseEventKind::Drag(mouse_button) => {
+                let ink = match mouse_button {
+                    MouseButton::Left => Ink::Primary,
+                    MouseButton::Right => Ink::Secondary,
+                    _ => return Ok(()),
+                };
+                let Some((x, y)) = self.hover else {
+                    return Ok(());
+                };
+                let tool = self.config.borrow().tool;
+                match tool {
+                    Tool::Pencil | Tool::Eraser => {
+                        let ink = if tool == Tool::Eraser {
+                            Ink::Erase
+                        } else {
+                            ink
+                        };
+                        match (&mouse.kind, &self.stroke) {
+                            (MouseEventKind::Drag(_), Some(_)) => self.stroke_to(x, y),
+                            _ => self.start_stroke(x, y, ink),
                         }
                     }
-                    _ => {}
+                    Tool::Gradient => {
+                        let start = match (&mouse.kind, self.gradient) {
+                            (MouseEventKind::Drag(_), Some(gradient)) => gradient.start,
+                            _ => (x, y),
+                        };
+                        self.gradient = Some(Gradient {
+                            ink,
+                            start,
+                            end: (x, y),
+                        });
+                    }
                 }
             }
-        } else {
-            // self.s = "canvas cood is None".into();
+            MouseEventKind::Up(_) => {
+                self.stroke = None;
+                if let Some(gradient) = self.gradient.take() {
+                    self.fill_gradient(gradient);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Fill the area under `start` with dithered gradient,
+    /// from the color of `ink` at `start` to the other color at `end`.
+    fn fill_gradient(&mut self, Gradient { ink, start, end }: Gradient) {
+        self.checkpoint();
+        let config = self.config.borrow();
+        let drawing = self.drawing.as_mut().unwrap();
+
+        let (from, to) = match ink {
+            Ink::Secondary => (&config.secondary_color, &config.color),
+            _ => (&config.color, &config.secondary_color),
+        };
+
+        let dx = end.0 as f32 - start.0 as f32;
+        let dy = end.1 as f32 - start.1 as f32;
+        // the end may be on another tile, so only the start is wrapped
+        let start = config.wrap(drawing, start.0, start.1);
+        let length = dx * dx + dy * dy;
+        for (x, y) in drawing.region(start.0, start.1) {
+            let t = if length > 0. {
+                let projection =
+                    (x as f32 - start.0 as f32) * dx + (y as f32 - start.1 as f32) * dy;
+                (projection / length).clamp(0., 1.)
+            } else {
+                0.
+            };
+            let color = if bayer(GRADIENT_BAYER_ORDER, x, y) < t {
+                to
+            } else {
+                from
+            };
+            if let Some(pixel) = drawing.pixel_mut(x, y) {
+                *pixel = config.blend.apply(color, pixel);
+            }
+        }
+    }
+
+    /// Paint with `ink` in the selection, or stamp the brush at the cursor when nothing is
+    /// selected.
+    fn paint_cursor(&mut self, ink: Ink) {
+        let (cursor, selection) = {
+            let config = self.config.borrow();
+            (config.cursor, config.selection)
+        };
+        let Some(selection) = selection else {
+            self.start_stroke(cursor.0, cursor.1, ink);
+            self.stroke = None;
+            return;
+        };
+        self.checkpoint();
+
+        let config = self.config.borrow();
+        let drawing = self.drawing.as_mut().unwrap();
+        for (x, y) in selection.points() {
+            let Some(pixel) = drawing.pixel_mut(x, y) else {
+                continue;
+            };
+            *pixel = match ink {
+                Ink::Erase => Color::from_rgba8(0, 0, 0, 0),
+                _ if config.pattern.secondary(x, y) => {
+                    config.blend.apply(&config.secondary_color, pixel)
+                }
+                _ => config.blend.apply(&config.color, pixel),
+            };
+        }
+    }
+
+    /// Record the drawing for undo before changing it.
+    fn checkpoint(&mut self) {
+        self.history.record(self.drawing.as_ref().unwrap());
+        self.changed();
+    }
+
+    /// Mark the drawing as changed since last written.
+    fn changed(&mut self) {
+        self.dirty = true;
+        self.edits = self.edits.wrapping_add(1);
+    }
+
+    /// Change colors of the drawing by `f` returning the number of changed pixels, and report it.
+    fn recolor(&mut self, f: impl FnOnce(&mut Drawing) -> usize) -> Result<()> {
+        let mut drawing = self.drawing.clone().unwrap();
+        let before = drawing.color_counts().len();
+        let changed = f(&mut drawing);
+        let after = drawing.color_counts().len();
+        if changed > 0 {
+            self.checkpoint();
+            self.drawing = Some(drawing);
+        }
+        self.tx.send(Event::Message(format!(
+            "{} pixels changed, {} colors to {}",
+            changed, before, after
+        )))?;
+        Ok(())
+    }
+
+    /// Apply transformation to the selection, or the whole drawing when nothing is selected.
+    ///
+    /// The transformed selection is placed at the top-left of the selection.
+    fn transform(&mut self, transform: Transform) {
+        self.checkpoint();
+        let mut config = self.config.borrow_mut();
+        let drawing = self.drawing.as_mut().unwrap();
+        let Some(selection) = config.selection.as_mut() else {
+            *drawing = transform.apply(drawing);
+            return;
+        };
+
+        let selected = drawing.crop(selection.x, selection.y, selection.width, selection.height);
+        let transformed = transform.apply(&selected);
+        drawing.clear(selection.x, selection.y, selection.width, selection.height);
+        drawing.paste(&transformed, selection.x, selection.y);
+        selection.width = transformed.width;
+        selection.height = transformed.height;
+    }
+
+    /// Start a new stroke at canvas position.
+    fn start_stroke(&mut self, x: usize, y: usize, ink: Ink) {
+        self.checkpoint();
+        let stroke = Stroke {
+            original: self.drawing.as_ref().unwrap().clone(),
+            points: vec![(x, y)],
+            ink,
+        };
+        self.paint(x, y, &stroke);
+        self.stroke = Some(stroke);
+    }
+
+    /// Continue current stroke to canvas position, filling the pixels in between.
+    fn stroke_to(&mut self, x: usize, y: usize) {
+        let Some(mut stroke) = self.stroke.take() else {
+            return;
+        };
+        let last = *stroke.points.last().unwrap();
+        let pixel_perfect =
+            self.config.borrow().pixel_perfect && self.config.borrow().brush.dimensions() == (1, 1);
+
+        for point in line(last, (x, y)).into_iter().skip(1) {
+            self.paint(point.0, point.1, &stroke);
+            stroke.points.push(point);
+
+            if pixel_perfect
+                && let [.., a, b, c] = stroke.points[..]
+                && is_corner(a, b, c)
+            {
+                // revert the corner, except pixels also painted by its neighbours
+                let keep = [self.stamp_points(a.0, a.1), self.stamp_points(c.0, c.1)].concat();
+                let corner = self.stamp_points(b.0, b.1);
+                let drawing = self.drawing.as_mut().unwrap();
+                for ((x, y), _) in corner {
+                    if !keep.iter().any(|(point, _)| *point == (x, y))
+                        && let (Some(pixel), Some(original)) =
+                            (drawing.pixel_mut(x, y), stroke.original.pixel(x, y))
+                    {
+                        *pixel = original.clone();
+                    }
+                }
+                stroke.points.remove(stroke.points.len() - 2);
+            }
+        }
+
+        self.stroke = Some(stroke);
+    }
+
+    /// Pixels covered by stamping the brush at canvas position,
+    /// including mirrored positions, with the stamp color for custom brush.
+    ///
+    /// In tile mode, pixels are wrapped into the drawing.
+    fn stamp_points(&self, x: usize, y: usize) -> Vec<((usize, usize), Option<Color>)> {
+        let drawing = self.drawing.as_ref().unwrap();
+        let config = self.config.borrow();
+
+        let mut points = vec![];
+        for &((dx, dy), ref stamp) in config.brush.footprint() {
+            let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+                continue;
+            };
+            let (x, y) = config.wrap(drawing, x, y);
+            for point in config.mirror.points(x, y, drawing.width, drawing.height) {
+                points.push((point, stamp.clone()));
+            }
+        }
+        points
+    }
+
+    /// Stamp the brush of the stroke at canvas position, and at its mirrored positions.
+    ///
+    /// Colors are blended onto the drawing before the stroke,
+    /// so pixels painted twice in a stroke are not blended twice.
+    fn paint(&mut self, x: usize, y: usize, stroke: &Stroke) {
+        let points = self.stamp_points(x, y);
+        let config = self.config.borrow();
+        let drawing = self.drawing.as_mut().unwrap();
+        let (main, other) = match stroke.ink {
+            Ink::Secondary => (&config.secondary_color, &config.color),
+            _ => (&config.color, &config.secondary_color),
+        };
+
+        for ((x, y), stamp) in points {
+            if let (Some(pixel), Some(original)) =
+                (drawing.pixel_mut(x, y), stroke.original.pixel(x, y))
+            {
+                let color = match (stroke.ink, stamp) {
+                    (Ink::Erase, _) => {
+                        *pixel = Color::from_rgba8(0, 0, 0, 0);
+                        continue;
+                    }
+                    (_, Some(stamp)) => stamp,
+                    (_, None) if config.pattern.secondary(x, y) => other.clone(),
+                    (_, None) => main.clone(),
+                };
+                *pixel = config.blend.apply(&color, original);
+            }
         }
     }
 
     /// Handle resize event.
     fn on_resize(&mut self) {
         // NOTE: window_size return size in both cells and pixels
-        self.window_size = crossterm::terminal::window_size().ok()
+        self.window_size = crossterm::terminal::window_size().ok();
+        // the screen is redrawn, so as the image
+        self.graphics_drawn = None;
+    }
+
+    /// Transform viewport position to the cell it falls in.
+    fn viewport_to_cell(&self, x: u16, y: u16) -> Option<(u16, u16)> {
+        let (cell_width, cell_height) = self.cell_size()?;
+        Some((x / cell_width, y / cell_height))
+    }
+
+    /// Size of a cell in the unit of mouse position.
+    fn cell_size(&self) -> Option<(u16, u16)> {
+        match self.config.borrow().mouse_mode {
+            MouseMode::Cell => Some((1, 1)),
+            MouseMode::Pixel => {
+                let window_size = self.window_size.as_ref()?;
+                let cell_width = window_size.width / window_size.columns.max(1);
+                let cell_height = window_size.height / window_size.rows.max(1);
+                // NOTE: pixel size may be reported as zero
+                (cell_width > 0 && cell_height > 1).then_some((cell_width, cell_height))
+            }
+        }
     }
 
     /// Transform viewport position to canvas position.
     ///
+    /// In cell mode, the half of cell is chosen by toggle, and `Alt` picks the other half.
+    ///
     /// Return `None` when position is outside canvas.
-    fn viewport_to_canvas(&self, x: u16, y: u16) -> Option<(u16, u16)> {
-        let window_size = self.window_size.as_ref()?;
+    fn viewport_to_canvas(&self, x: u16, y: u16, modifiers: KeyModifiers) -> Option<(u16, u16)> {
         let canvas_area = self.canvas_area.as_ref()?;
 
-        let cell_width = window_size.width / window_size.columns;
-        let cell_height = window_size.height / window_size.rows;
-        if !canvas_area.contains((x / cell_width, y / cell_height).into()) {
+        let (_, cell_height) = self.cell_size()?;
+        let (column, row) = self.viewport_to_cell(x, y)?;
+        if !canvas_area.contains((column, row).into()) {
             return None;
         }
-        let x_pixel = x / cell_width - canvas_area.x;
-        let y_pixel = (y - canvas_area.y * cell_height) / (cell_height / 2);
+        let x_pixel = column - canvas_area.x;
+        let y_pixel = match self.config.borrow().mouse_mode {
+            MouseMode::Cell => {
+                let lower = self.config.borrow().lower_half ^ modifiers.contains(KeyModifiers::ALT);
+                (row - canvas_area.y) * 2 + lower as u16
+            }
+            MouseMode::Pixel => (y - canvas_area.y * cell_height) / (cell_height / 2),
+        };
         Some((x_pixel, y_pixel))
     }
 }
+
+/// Whether loading failed because the file does not exist.
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|error| error.kind() == std::io::ErrorKind::NotFound)
+}
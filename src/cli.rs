@@ -1,10 +1,71 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::{
+    drawing::scale::{Filter, Size},
+    export::sheet::Layout,
+    graphics::Graphics,
+    utils::{color_depth::ColorDepth, mouse::MouseMode},
+};
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
     // TODO: currently force use a path, may be optional for new file
     /// The file path to load and save.
     pub path: Option<PathBuf>,
+    /// How to draw the canvas, detected from the terminal when omitted.
+    #[arg(long, value_enum)]
+    pub graphics: Option<Graphics>,
+    /// Colors supported by the terminal, detected from `COLORTERM` and `TERM` when omitted.
+    #[arg(long, value_enum)]
+    pub colors: Option<ColorDepth>,
+    /// Dither the canvas when colors are limited.
+    #[arg(long)]
+    pub dither: bool,
+    /// Mouse position unit, pixel mode when the terminal reports pixel size if omitted.
+    #[arg(long, value_enum)]
+    pub mouse: Option<MouseMode>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Convert drawings without opening the editor.
+    Export(ExportArgs),
+}
+
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    /// The drawings to export, as frames of animation.
+    #[arg(required = true, num_args = 1..)]
+    pub inputs: Vec<PathBuf>,
+    /// Output file, format by extension: `gif`, `png`, `aseprite` or `json`.
+    pub output: PathBuf,
+    /// Resample by factor like `2` or to size like `32x16`.
+    #[arg(long)]
+    pub scale: Option<Size>,
+    /// Resampling algorithm.
+    #[arg(long, value_enum, default_value_t)]
+    pub filter: Filter,
+    /// Delay of frames in milliseconds, comma separated for each frame.
+    #[arg(long, value_delimiter = ',')]
+    pub delay: Vec<u32>,
+    /// Times to replay the animation, forever when omitted.
+    #[arg(long)]
+    pub repeat: Option<u16>,
+    /// Pack frames into a spritesheet PNG, with a JSON atlas of the same name.
+    #[arg(long, value_enum)]
+    pub sheet: Option<Layout>,
+    /// Columns of grid spritesheet.
+    #[arg(long)]
+    pub columns: Option<usize>,
+    /// Pixels between frames of spritesheet.
+    #[arg(long, default_value_t = 0)]
+    pub padding: usize,
+    /// Pixels to repeat frame edges by in spritesheet, against bleeding when sampled.
+    #[arg(long, default_value_t = 0)]
+    pub extrude: usize,
 }
@@ -0,0 +1,633 @@
+//! [Aseprite file format](https://github.com/aseprite/aseprite/blob/main/docs/ase-file-specs.md).
+//!
+//! Visible layers of the first frame are flattened into one drawing,
+//! drawings are written as frames of one layer in RGBA mode.
+
+use std::io::{Read, Write};
+
+use anyhow::{Context, Result, bail};
+use csscolorparser::Color;
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+
+use crate::drawing::{Drawing, MAX_SIZE, color::ColorExt};
+
+const HEADER_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const HEADER_SIZE: usize = 128;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_OLD_PALETTE_64: u16 = 0x0011;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_COLOR_PROFILE: u16 = 0x2007;
+const CHUNK_PALETTE: u16 = 0x2019;
+
+const LAYER_VISIBLE: u16 = 1;
+const LAYER_EDITABLE: u16 = 2;
+/// Header flag telling layer opacity is valid.
+const LAYER_OPACITY_VALID: u32 = 1;
+
+const CEL_RAW: u16 = 0;
+const CEL_LINKED: u16 = 1;
+const CEL_COMPRESSED: u16 = 2;
+
+/// Little-endian reader of file data.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + len)
+            .context("unexpected end of aseprite file")?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn word(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn short(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn dword(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.word()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+}
+
+struct Layer {
+    /// Visible, including its parent groups.
+    visible: bool,
+    opacity: u8,
+    blend_mode: u16,
+}
+
+/// Parse aseprite file, with warnings of what could not be loaded.
+pub fn parse(data: &[u8]) -> Result<(Drawing, Vec<String>)> {
+    let mut reader = Reader::new(data);
+    reader.dword()?;
+    if reader.word()? != HEADER_MAGIC {
+        bail!("not an aseprite file");
+    }
+    let frames = reader.word()?;
+    let width = reader.word()? as usize;
+    let height = reader.word()? as usize;
+    let depth = reader.word()?;
+    let flags = reader.dword()?;
+    reader.word()?;
+    reader.dword()?;
+    reader.dword()?;
+    let transparent_index = reader.byte()?;
+    if width == 0 || height == 0 {
+        bail!("empty aseprite sprite");
+    }
+    if width > MAX_SIZE || height > MAX_SIZE {
+        bail!(
+            "aseprite sprite {}x{} exceeds the maximum {}x{}",
+            width,
+            height,
+            MAX_SIZE,
+            MAX_SIZE
+        );
+    }
+    if !matches!(depth, 8 | 16 | 32) {
+        bail!("unsupported color depth: {}", depth);
+    }
+    reader.position = HEADER_SIZE;
+
+    let mut warnings = vec![];
+    let mut warn = |warning: String| {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    };
+    if frames > 1 {
+        warn(format!("only the first of {} frames is loaded", frames));
+    }
+    match depth {
+        8 => warn("indexed color mode is converted to RGBA".to_string()),
+        16 => warn("grayscale color mode is converted to RGBA".to_string()),
+        _ => {}
+    }
+
+    reader.dword()?;
+    if reader.word()? != FRAME_MAGIC {
+        bail!("invalid aseprite frame");
+    }
+    let old_chunks = reader.word()?;
+    reader.bytes(4)?;
+    let chunks = match reader.dword()? {
+        0 => old_chunks as u32,
+        n => n,
+    };
+
+    let mut palette = vec![Color::new(0., 0., 0., 0.); 256];
+    let mut layers: Vec<Layer> = vec![];
+    // visibility of the groups containing the next layer, by child level
+    let mut groups: Vec<bool> = vec![];
+    let mut cels = vec![];
+    let mut visible_layers = 0;
+    for _ in 0..chunks {
+        let start = reader.position;
+        let size = reader.dword()? as usize;
+        let kind = reader.word()?;
+        if size < 6 {
+            bail!("invalid aseprite chunk size");
+        }
+        let mut chunk = Reader::new(reader.bytes(size - 6)?);
+        match kind {
+            CHUNK_LAYER => {
+                let layer_flags = chunk.word()?;
+                let layer_type = chunk.word()?;
+                let level = chunk.word()? as usize;
+                chunk.bytes(4)?;
+                let blend_mode = chunk.word()?;
+                let opacity = chunk.byte()?;
+                chunk.bytes(3)?;
+                let name = chunk.string()?;
+
+                groups.truncate(level);
+                let visible =
+                    layer_flags & LAYER_VISIBLE != 0 && groups.iter().all(|visible| *visible);
+                match layer_type {
+                    0 if visible => visible_layers += 1,
+                    0 => warn(format!("hidden layer {:?} is dropped", name)),
+                    1 => groups.push(visible),
+                    _ => warn(format!("tilemap layer {:?} is not supported", name)),
+                }
+                layers.push(Layer {
+                    visible: visible && layer_type == 0,
+                    opacity: if flags & LAYER_OPACITY_VALID != 0 {
+                        opacity
+                    } else {
+                        255
+                    },
+                    blend_mode,
+                });
+            }
+            // cels are drawn after palette and all layers are known
+            CHUNK_CEL => cels.push(chunk),
+            CHUNK_PALETTE => {
+                chunk.dword()?;
+                let first = chunk.dword()? as usize;
+                let last = chunk.dword()? as usize;
+                chunk.bytes(8)?;
+                for index in first..=last {
+                    let entry_flags = chunk.word()?;
+                    let [r, g, b, a] = chunk.bytes(4)?.try_into()?;
+                    if entry_flags & 1 != 0 {
+                        chunk.string()?;
+                    }
+                    if let Some(color) = palette.get_mut(index) {
+                        *color = Color::from_rgba8(r, g, b, a);
+                    }
+                }
+            }
+            CHUNK_OLD_PALETTE | CHUNK_OLD_PALETTE_64 => {
+                // colors of 0-63 are scaled up
+                let scale = if kind == CHUNK_OLD_PALETTE_64 { 4 } else { 1 };
+                let mut index = 0;
+                for _ in 0..chunk.word()? {
+                    index += chunk.byte()? as usize;
+                    let count = match chunk.byte()? {
+                        0 => 256,
+                        n => n as usize,
+                    };
+                    for _ in 0..count {
+                        let [r, g, b] = chunk.bytes(3)?.try_into()?;
+                        if let Some(color) = palette.get_mut(index) {
+                            *color = Color::from_rgba8(
+                                r.saturating_mul(scale),
+                                g.saturating_mul(scale),
+                                b.saturating_mul(scale),
+                                255,
+                            );
+                        }
+                        index += 1;
+                    }
+                }
+            }
+            CHUNK_COLOR_PROFILE => {}
+            _ => warn(format!("{} chunk is ignored", chunk_name(kind))),
+        }
+        reader.position = start + size;
+    }
+    if visible_layers > 1 {
+        warn(format!("{} layers are flattened into one", visible_layers));
+    }
+    if depth == 8 {
+        palette[transparent_index as usize] = Color::new(0., 0., 0., 0.);
+    }
+
+    // cels are in layer order, bottom first
+    let mut drawing = Drawing::new(width, height);
+    let mut cels = cels
+        .into_iter()
+        .map(|mut chunk| Ok((chunk.word()? as usize, chunk)))
+        .collect::<Result<Vec<_>>>()?;
+    cels.sort_by_key(|(layer, _)| *layer);
+    for (layer_index, mut chunk) in cels {
+        let Some(layer) = layers.get(layer_index) else {
+            bail!("cel of unknown layer {}", layer_index);
+        };
+        if !layer.visible {
+            continue;
+        }
+        if layer.blend_mode != 0 {
+            warn(format!(
+                "blend mode {} is drawn as normal",
+                blend_mode_name(layer.blend_mode)
+            ));
+        }
+        let x = chunk.short()? as isize;
+        let y = chunk.short()? as isize;
+        let opacity = chunk.byte()? as f32 / 255. * layer.opacity as f32 / 255.;
+        let cel_type = chunk.word()?;
+        chunk.bytes(7)?;
+
+        let (cel_width, cel_height) = match cel_type {
+            CEL_RAW | CEL_COMPRESSED => (chunk.word()? as usize, chunk.word()? as usize),
+            CEL_LINKED => continue,
+            _ => {
+                warn("tilemap cel is not supported".to_string());
+                continue;
+            }
+        };
+        if cel_width > width || cel_height > height {
+            bail!("cel {}x{} is larger than the sprite", cel_width, cel_height);
+        }
+        let bytes_per_pixel = depth as usize / 8;
+        let len = cel_width * cel_height * bytes_per_pixel;
+        let pixels = if cel_type == CEL_COMPRESSED {
+            let mut pixels = vec![];
+            ZlibDecoder::new(&chunk.data[chunk.position..])
+                .take(len as u64)
+                .read_to_end(&mut pixels)
+                .context("invalid compressed cel")?;
+            pixels
+        } else {
+            chunk.bytes(len)?.to_vec()
+        };
+        if pixels.len() < len {
+            bail!("incomplete cel pixels");
+        }
+
+        for (i, pixel) in pixels.chunks_exact(bytes_per_pixel).enumerate() {
+            let mut color = match *pixel {
+                [r, g, b, a] => Color::from_rgba8(r, g, b, a),
+                [value, a] => Color::from_rgba8(value, value, value, a),
+                [index] => palette[index as usize].clone(),
+                _ => unreachable!(),
+            };
+            color.a *= opacity;
+            let (Some(px), Some(py)) = (
+                x.checked_add_unsigned(i % cel_width),
+                y.checked_add_unsigned(i / cel_width),
+            ) else {
+                continue;
+            };
+            if let (Ok(px), Ok(py)) = (usize::try_from(px), usize::try_from(py))
+                && let Some(dst) = drawing.pixel_mut(px, py)
+            {
+                // copied as is over transparent pixels, keeping exact colors
+                *dst = if dst.a > 0. { color.over(dst) } else { color };
+            }
+        }
+    }
+
+    Ok((drawing, warnings))
+}
+
+/// Encode frames of the same size as aseprite file, with duration of each frame in milliseconds.
+pub fn encode(frames: &[Drawing], durations: &[u16]) -> Result<Vec<u8>> {
+    let Some(first) = frames.first() else {
+        bail!("no frame to export");
+    };
+    let (width, height) = (first.width, first.height);
+    if frames
+        .iter()
+        .any(|f| (f.width, f.height) != (width, height))
+    {
+        bail!("all frames should have the same size");
+    }
+    let (Ok(width), Ok(height), Ok(count)) = (
+        u16::try_from(width),
+        u16::try_from(height),
+        u16::try_from(frames.len()),
+    ) else {
+        bail!("drawing too large for aseprite");
+    };
+
+    // palette of used colors, informative in RGBA mode
+    let mut palette: Vec<[u8; 4]> = vec![];
+    for color in frames.iter().flat_map(|f| &f.pixels) {
+        let color = color.to_rgba8();
+        if palette.len() < 256 && !palette.contains(&color) {
+            palette.push(color);
+        }
+    }
+
+    let mut body = vec![];
+    for (index, frame) in frames.iter().enumerate() {
+        let mut chunks = vec![];
+        if index == 0 {
+            let mut data = vec![];
+            data.extend((palette.len() as u32).to_le_bytes());
+            data.extend(0u32.to_le_bytes());
+            data.extend((palette.len() as u32).saturating_sub(1).to_le_bytes());
+            data.extend([0; 8]);
+            for color in &palette {
+                data.extend(0u16.to_le_bytes());
+                data.extend(color);
+            }
+            chunks.push(chunk(CHUNK_PALETTE, &data));
+
+            let name = "Layer 1";
+            let mut data = vec![];
+            data.extend((LAYER_VISIBLE | LAYER_EDITABLE).to_le_bytes());
+            // type, child level, default size and blend mode
+            data.extend([0; 10]);
+            data.push(255);
+            data.extend([0; 3]);
+            data.extend((name.len() as u16).to_le_bytes());
+            data.extend(name.as_bytes());
+            chunks.push(chunk(CHUNK_LAYER, &data));
+        }
+
+        let mut data = vec![];
+        // layer, position
+        data.extend([0; 6]);
+        data.push(255);
+        data.extend(CEL_COMPRESSED.to_le_bytes());
+        data.extend([0; 7]);
+        data.extend(width.to_le_bytes());
+        data.extend(height.to_le_bytes());
+        let mut encoder = ZlibEncoder::new(data, Compression::default());
+        for color in &frame.pixels {
+            encoder.write_all(&color.to_rgba8())?;
+        }
+        chunks.push(chunk(CHUNK_CEL, &encoder.finish()?));
+
+        let chunks_data = chunks.concat();
+        let duration = durations.get(index).copied().unwrap_or(100);
+        body.extend((chunks_data.len() as u32 + 16).to_le_bytes());
+        body.extend(FRAME_MAGIC.to_le_bytes());
+        body.extend((chunks.len() as u16).to_le_bytes());
+        body.extend(duration.to_le_bytes());
+        body.extend([0; 2]);
+        body.extend((chunks.len() as u32).to_le_bytes());
+        body.extend(chunks_data);
+    }
+
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend(((HEADER_SIZE + body.len()) as u32).to_le_bytes());
+    header.extend(HEADER_MAGIC.to_le_bytes());
+    header.extend(count.to_le_bytes());
+    header.extend(width.to_le_bytes());
+    header.extend(height.to_le_bytes());
+    header.extend(32u16.to_le_bytes());
+    header.extend(LAYER_OPACITY_VALID.to_le_bytes());
+    // deprecated speed
+    header.extend(100u16.to_le_bytes());
+    header.extend([0; 8]);
+    // transparent index
+    header.extend([0; 4]);
+    // 0 means 256 colors
+    header.extend((palette.len() as u16 % 256).to_le_bytes());
+    // pixel ratio
+    header.extend([1, 1]);
+    // grid
+    header.extend([0; 4]);
+    header.extend(16u16.to_le_bytes());
+    header.extend(16u16.to_le_bytes());
+    header.resize(HEADER_SIZE, 0);
+
+    Ok([header, body].concat())
+}
+
+/// Chunk of `kind` with data.
+fn chunk(kind: u16, data: &[u8]) -> Vec<u8> {
+    [
+        &(data.len() as u32 + 6).to_le_bytes()[..],
+        &kind.to_le_bytes(),
+        data,
+    ]
+    .concat()
+}
+
+fn chunk_name(kind: u16) -> String {
+    match kind {
+        0x2006 => "cel extra".to_string(),
+        0x2008 => "external files".to_string(),
+        0x2016 => "mask".to_string(),
+        0x2017 => "path".to_string(),
+        0x2018 => "tags".to_string(),
+        0x2020 => "user data".to_string(),
+        0x2022 => "slice".to_string(),
+        0x2023 => "tileset".to_string(),
+        _ => format!("unknown 0x{:04x}", kind),
+    }
+}
+
+fn blend_mode_name(mode: u16) -> &'static str {
+    match mode {
+        1 => "multiply",
+        2 => "screen",
+        3 => "overlay",
+        4 => "darken",
+        5 => "lighten",
+        6 => "color dodge",
+        7 => "color burn",
+        8 => "hard light",
+        9 => "soft light",
+        10 => "difference",
+        11 => "exclusion",
+        12 => "hue",
+        13 => "saturation",
+        14 => "color",
+        15 => "luminosity",
+        16 => "addition",
+        17 => "subtract",
+        18 => "divide",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::drawing::fixtures::{SAMPLES, rgba};
+
+    fn layer(flags: u16, name: &str) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend(flags.to_le_bytes());
+        data.extend([0; 10]);
+        data.push(255);
+        data.extend([0; 3]);
+        data.extend((name.len() as u16).to_le_bytes());
+        data.extend(name.as_bytes());
+        chunk(CHUNK_LAYER, &data)
+    }
+
+    fn compressed_cel(layer: u16, x: i16, width: u16, height: u16, pixels: &[u8]) -> Vec<u8> {
+        let mut data = vec![];
+        data.extend(layer.to_le_bytes());
+        data.extend(x.to_le_bytes());
+        data.extend(0i16.to_le_bytes());
+        data.push(255);
+        data.extend(CEL_COMPRESSED.to_le_bytes());
+        data.extend([0; 7]);
+        data.extend(width.to_le_bytes());
+        data.extend(height.to_le_bytes());
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(pixels).unwrap();
+        data.extend(encoder.finish().unwrap());
+        chunk(CHUNK_CEL, &data)
+    }
+
+    fn file(width: u16, height: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        file_with_depth(width, height, 32, chunks)
+    }
+
+    fn file_with_depth(width: u16, height: u16, depth: u16, chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut frame = vec![];
+        frame.extend((body.len() as u32 + 16).to_le_bytes());
+        frame.extend(FRAME_MAGIC.to_le_bytes());
+        frame.extend((chunks.len() as u16).to_le_bytes());
+        frame.extend(100u16.to_le_bytes());
+        frame.extend([0; 2]);
+        frame.extend((chunks.len() as u32).to_le_bytes());
+        frame.extend(body);
+
+        let mut header = vec![0; HEADER_SIZE];
+        header[0..4].copy_from_slice(&((HEADER_SIZE + frame.len()) as u32).to_le_bytes());
+        header[4..6].copy_from_slice(&HEADER_MAGIC.to_le_bytes());
+        header[6..8].copy_from_slice(&1u16.to_le_bytes());
+        header[8..10].copy_from_slice(&width.to_le_bytes());
+        header[10..12].copy_from_slice(&height.to_le_bytes());
+        header[12..14].copy_from_slice(&depth.to_le_bytes());
+        header[14..18].copy_from_slice(&LAYER_OPACITY_VALID.to_le_bytes());
+        [header, frame].concat()
+    }
+
+    #[test]
+    fn test_parse_flattens_visible_layers() {
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let data = file(
+            2,
+            1,
+            &[
+                layer(LAYER_VISIBLE, "bottom"),
+                layer(LAYER_VISIBLE, "top"),
+                layer(0, "hidden"),
+                compressed_cel(0, 0, 2, 1, &[red, red].concat()),
+                compressed_cel(1, 1, 1, 1, &blue),
+                compressed_cel(2, 0, 1, 1, &blue),
+                chunk(0x2018, &[]),
+            ],
+        );
+        let (drawing, warnings) = parse(&data).unwrap();
+        assert_eq!((drawing.width, drawing.height), (2, 1));
+        assert_eq!(drawing.pixels[0].to_rgba8(), red);
+        assert_eq!(drawing.pixels[1].to_rgba8(), blue);
+        assert_eq!(
+            warnings,
+            [
+                "hidden layer \"hidden\" is dropped",
+                "tags chunk is ignored",
+                "2 layers are flattened into one"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_color_modes() {
+        let data = file_with_depth(
+            2,
+            1,
+            16,
+            &[
+                layer(LAYER_VISIBLE, "layer"),
+                compressed_cel(0, 0, 2, 1, &[0, 255, 255, 128]),
+            ],
+        );
+        let (drawing, warnings) = parse(&data).unwrap();
+        assert_eq!(rgba(&drawing), [[0, 0, 0, 255], [255, 255, 255, 128]]);
+        assert_eq!(warnings, ["grayscale color mode is converted to RGBA"]);
+
+        let data = file_with_depth(1, 1, 8, &[layer(LAYER_VISIBLE, "layer")]);
+        let (_, warnings) = parse(&data).unwrap();
+        assert_eq!(warnings, ["indexed color mode is converted to RGBA"]);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse(b"not aseprite").is_err());
+        let data = file(1, 1, &[layer(LAYER_VISIBLE, "layer")]);
+        assert!(parse(&data[..data.len() - 1]).is_err());
+
+        let data = file(65535, 65535, &[layer(LAYER_VISIBLE, "layer")]);
+        assert!(
+            parse(&data)
+                .unwrap_err()
+                .to_string()
+                .contains("exceeds the maximum")
+        );
+
+        let cel = compressed_cel(0, 0, 2, 1, &[0; 8]);
+        let data = file(1, 1, &[layer(LAYER_VISIBLE, "layer"), cel]);
+        assert_eq!(
+            parse(&data).unwrap_err().to_string(),
+            "cel 2x1 is larger than the sprite"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_samples() {
+        for sample in SAMPLES {
+            let mut drawing = serde_json::from_str::<Drawing>(sample).unwrap();
+            assert!(drawing.validate());
+            let data = encode(std::slice::from_ref(&drawing), &[100]).unwrap();
+            let (parsed, warnings) = parse(&data).unwrap();
+            assert!(warnings.is_empty());
+            assert_eq!(
+                (parsed.width, parsed.height),
+                (drawing.width, drawing.height)
+            );
+            assert_eq!(rgba(&parsed), rgba(&drawing));
+        }
+    }
+
+    #[test]
+    fn test_encode_frames() {
+        let frames = [Drawing::new(2, 2), Drawing::new(2, 2)];
+        let data = encode(&frames, &[50, 50]).unwrap();
+        assert_eq!(
+            data.len() as u32,
+            u32::from_le_bytes(data[..4].try_into().unwrap())
+        );
+        let (_, warnings) = parse(&data).unwrap();
+        assert_eq!(warnings, ["only the first of 2 frames is loaded"]);
+        assert!(encode(&[Drawing::new(1, 1), Drawing::new(2, 1)], &[]).is_err());
+    }
+}
@@ -2,15 +2,26 @@
 
 use csscolorparser::Color;
 
+use crate::utils::color_depth::ColorDepth;
+
 pub trait ColorExt {
-    fn to_ratatui(&self, bg: [u8; 3]) -> ratatui::style::Color;
+    /// Terminal color when drawn over `bg`.
+    fn to_ratatui(&self, bg: [u8; 3], depth: ColorDepth) -> ratatui::style::Color;
+    /// Opaque color when drawn over `bg`.
+    fn to_rgb(&self, bg: [u8; 3]) -> [u8; 3];
     fn grayscale(&self) -> u8;
     /// Which fg (black or white) to use when use self as background.
     fn calculate_fg(&self) -> Color;
+    /// Alpha composite self over `dst` (source-over).
+    fn over(&self, dst: &Color) -> Color;
 }
 
 impl ColorExt for Color {
-    fn to_ratatui(&self, bg: [u8; 3]) -> ratatui::style::Color {
+    fn to_ratatui(&self, bg: [u8; 3], depth: ColorDepth) -> ratatui::style::Color {
+        depth.convert(self.to_rgb(bg))
+    }
+
+    fn to_rgb(&self, bg: [u8; 3]) -> [u8; 3] {
         let [r, g, b, a] = self.to_rgba8();
         let [bg_r, bg_g, bg_b] = bg;
 
@@ -19,7 +30,7 @@ impl ColorExt for Color {
             (a * x as f32 + (1. - a) * bg_x as f32) as u8
         };
 
-        ratatui::style::Color::Rgb(blend(r, bg_r), blend(g, bg_g), blend(b, bg_b))
+        [blend(r, bg_r), blend(g, bg_g), blend(b, bg_b)]
     }
 
     fn grayscale(&self) -> u8 {
@@ -34,6 +45,20 @@ impl ColorExt for Color {
             Color::from_rgba8(255, 255, 255, 255)
         }
     }
+
+    fn over(&self, dst: &Color) -> Color {
+        let a = self.a + dst.a * (1. - self.a);
+        if a <= 0. {
+            return Color::new(0., 0., 0., 0.);
+        }
+        let blend = |x: f32, dst_x: f32| (x * self.a + dst_x * dst.a * (1. - self.a)) / a;
+        Color::new(
+            blend(self.r, dst.r),
+            blend(self.g, dst.g),
+            blend(self.b, dst.b),
+            a,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -48,4 +73,14 @@ mod test {
         let grayscale = white.grayscale();
         assert_eq!(grayscale, 255);
     }
+
+    #[test]
+    fn test_over() {
+        let red = Color::from_rgba8(255, 0, 0, 128);
+        let blue = Color::from_rgba8(0, 0, 255, 255);
+        assert_eq!(red.over(&blue).to_rgba8(), [128, 0, 127, 255]);
+
+        let transparent = Color::from_rgba8(0, 0, 0, 0);
+        assert_eq!(red.over(&transparent).to_rgba8(), red.to_rgba8());
+    }
 }
@@ -0,0 +1,43 @@
+//! Ordered dithering.
+
+/// Threshold in `[0, 1)` of the Bayer matrix of given order at `(x, y)`.
+///
+/// `order` must be a power of two, such as 2, 4 or 8.
+pub fn bayer(order: usize, x: usize, y: usize) -> f32 {
+    let (mut x, mut y) = (x % order, y % order);
+    let mut value = 0;
+    for _ in 0..order.trailing_zeros() {
+        value = (value << 2) | (((x ^ y) & 1) << 1) | (y & 1);
+        x >>= 1;
+        y >>= 1;
+    }
+    (value as f32 + 0.5) / (order * order) as f32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bayer() {
+        let matrix = |order| {
+            (0..order)
+                .map(|y| {
+                    (0..order)
+                        .map(|x| (bayer(order, x, y) * (order * order) as f32) as usize)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matrix(2), vec![vec![0, 2], vec![3, 1]]);
+        assert_eq!(
+            matrix(4),
+            vec![
+                vec![0, 8, 2, 10],
+                vec![12, 4, 14, 6],
+                vec![3, 11, 1, 9],
+                vec![15, 7, 13, 5]
+            ]
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Shared test data and helpers.
+
+use csscolorparser::Color;
+
+use crate::drawing::Drawing;
+
+/// Sample drawing files, in the legacy format without version.
+pub const SAMPLES: [&str; 4] = [
+    include_str!("../../samples/16x16.json"),
+    include_str!("../../samples/8x8.json"),
+    include_str!("../../samples/fruit.json"),
+    include_str!("../../samples/rgbw.json"),
+];
+
+/// Pixels as RGBA bytes.
+pub fn rgba(drawing: &Drawing) -> Vec<[u8; 4]> {
+    drawing.pixels.iter().map(Color::to_rgba8).collect()
+}
@@ -0,0 +1,202 @@
+//! Native file format.
+//!
+//! Version 2 stores pixels as base64 of zlib compressed RGBA bytes:
+//!
+//! ```json
+//! {"version":2,"width":16,"height":16,"compression":"zlib","pixels":"eJz..."}
+//! ```
+//!
+//! Files without version are version 1, with pixels as an array of CSS colors.
+
+use std::io::{Read, Write};
+
+use anyhow::{Context, Result, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use csscolorparser::Color;
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use serde::{Deserialize, Serialize};
+
+use crate::drawing::{Drawing, MAX_SIZE};
+
+/// Version written by this build.
+pub const VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct File {
+    version: u32,
+    width: usize,
+    height: usize,
+    /// `zlib`, or `None` for plain bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression: Option<String>,
+    /// Base64 of RGBA bytes.
+    pixels: String,
+}
+
+/// Decode drawing file of any version.
+pub fn decode(text: &str) -> Result<Drawing> {
+    let value = serde_json::from_str::<serde_json::Value>(text).context("invalid JSON")?;
+    let version = match value.get("version") {
+        None => 1,
+        Some(version) => version.as_u64().context("invalid version")?,
+    };
+    if version == 0 {
+        bail!("invalid version 0");
+    }
+
+    let mut drawing = match version {
+        1 => serde_json::from_value::<Drawing>(value).context("invalid drawing")?,
+        2 => {
+            let file = serde_json::from_value::<File>(value).context("invalid drawing")?;
+            let len = pixel_count(file.width, file.height)? * 4;
+            let bytes = STANDARD
+                .decode(&file.pixels)
+                .context("invalid base64 pixels")?;
+            let bytes = match file.compression.as_deref() {
+                None => bytes,
+                Some("zlib") => {
+                    // one more byte to tell there are too many
+                    let mut decompressed = vec![];
+                    ZlibDecoder::new(&bytes[..])
+                        .take(len as u64 + 1)
+                        .read_to_end(&mut decompressed)
+                        .context("invalid compressed pixels")?;
+                    decompressed
+                }
+                Some(compression) => bail!("unknown compression: {}", compression),
+            };
+            if bytes.len() > len {
+                bail!("too many pixels for {}x{} drawing", file.width, file.height);
+            }
+            if bytes.len() % 4 != 0 {
+                bail!("pixels are not RGBA bytes");
+            }
+            Drawing {
+                width: file.width,
+                height: file.height,
+                pixels: bytes
+                    .chunks_exact(4)
+                    .map(|c| Color::from_rgba8(c[0], c[1], c[2], c[3]))
+                    .collect(),
+            }
+        }
+        _ => bail!(
+            "file version {} is newer than supported version {}, please update tuisprite",
+            version,
+            VERSION
+        ),
+    };
+
+    let count = pixel_count(drawing.width, drawing.height)?;
+    if !drawing.validate() {
+        bail!(
+            "expected {} pixels for {}x{} drawing, found {}",
+            count,
+            drawing.width,
+            drawing.height,
+            drawing.pixels.len()
+        );
+    }
+    Ok(drawing)
+}
+
+/// Number of pixels of a drawing of `width` x `height`, if the size is supported.
+fn pixel_count(width: usize, height: usize) -> Result<usize> {
+    if width == 0 || height == 0 {
+        bail!("drawing size {}x{} is empty", width, height);
+    }
+    if width > MAX_SIZE || height > MAX_SIZE {
+        bail!(
+            "drawing size {}x{} exceeds the maximum {}x{}",
+            width,
+            height,
+            MAX_SIZE,
+            MAX_SIZE
+        );
+    }
+    width.checked_mul(height).context("drawing size overflows")
+}
+
+/// Encode drawing in the current version.
+pub fn encode(drawing: &Drawing) -> Result<String> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+    for color in &drawing.pixels {
+        encoder.write_all(&color.to_rgba8())?;
+    }
+    let file = File {
+        version: VERSION,
+        width: drawing.width,
+        height: drawing.height,
+        compression: Some("zlib".to_string()),
+        pixels: STANDARD.encode(encoder.finish()?),
+    };
+    Ok(serde_json::to_string(&file)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::drawing::fixtures::{SAMPLES, rgba};
+
+    #[test]
+    fn test_migrate_samples() {
+        for sample in SAMPLES {
+            let drawing = decode(sample).unwrap();
+            let encoded = encode(&drawing).unwrap();
+            assert!(encoded.len() <= sample.len().max(100));
+            let decoded = decode(&encoded).unwrap();
+            assert_eq!(
+                (decoded.width, decoded.height),
+                (drawing.width, drawing.height)
+            );
+            assert_eq!(rgba(&decoded), rgba(&drawing));
+        }
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let text = r#"{"version":2,"width":1,"height":1,"pixels":"/wAA/w=="}"#;
+        assert_eq!(rgba(&decode(text).unwrap()), [[255, 0, 0, 255]]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |text: &str| decode(text).unwrap_err().to_string();
+        assert_eq!(error("{"), "invalid JSON");
+        assert!(error(r#"{"version":3}"#).contains("newer than supported"));
+        assert_eq!(error(r#"{"version":0}"#), "invalid version 0");
+        assert_eq!(error(r#"{"version":"2"}"#), "invalid version");
+        assert_eq!(
+            error(r#"{"width":4294967296,"height":4294967297}"#),
+            "drawing size 4294967296x4294967297 exceeds the maximum 4096x4096"
+        );
+        assert!(error(r#"{"width":100000,"height":100000}"#).contains("exceeds the maximum"));
+        assert!(
+            error(r#"{"version":2,"width":100000,"height":1,"pixels":""}"#)
+                .contains("exceeds the maximum")
+        );
+        assert_eq!(
+            error(r##"{"width":2,"height":1,"pixels":["#fff"]}"##),
+            "expected 2 pixels for 2x1 drawing, found 1"
+        );
+        assert_eq!(
+            error(r#"{"width":0,"height":1}"#),
+            "drawing size 0x1 is empty"
+        );
+    }
+
+    #[test]
+    fn test_zlib_bomb() {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&vec![0; 8 << 20]).unwrap();
+        let pixels = STANDARD.encode(encoder.finish().unwrap());
+        let text = format!(
+            r#"{{"version":2,"width":1,"height":1,"compression":"zlib","pixels":"{}"}}"#,
+            pixels
+        );
+        assert_eq!(
+            decode(&text).unwrap_err().to_string(),
+            "too many pixels for 1x1 drawing"
+        );
+    }
+}
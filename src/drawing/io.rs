@@ -1,11 +1,91 @@
 use anyhow::Result;
+use csscolorparser::Color;
+use image::ImageFormat;
 use std::path::Path;
 
-use crate::drawing::Drawing;
+use crate::drawing::{Drawing, aseprite, format};
 
-pub async fn load_drawing_from_file(path: &Path) -> Result<Drawing> {
+/// Whether the file at `path` is an aseprite file, by extension.
+pub fn is_aseprite(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ase") || ext.eq_ignore_ascii_case("aseprite"))
+}
+
+/// Load drawing from native or aseprite file, with warnings of what could not be loaded.
+pub async fn load_drawing_from_file(path: &Path) -> Result<(Drawing, Vec<String>)> {
+    if is_aseprite(path) {
+        let data = tokio::fs::read(path).await?;
+        return aseprite::parse(&data);
+    }
     let text = tokio::fs::read_to_string(path).await?;
-    let mut drawing = serde_json::from_str::<Drawing>(&text)?;
-    drawing.validate();
-    Ok(drawing)
+    Ok((format::decode(&text)?, vec![]))
+}
+
+/// Load image of any supported format, such as PNG, JPEG, BMP or GIF.
+pub async fn load_image(path: &Path) -> Result<Drawing> {
+    let data = tokio::fs::read(path).await?;
+    let image = image::load_from_memory(&data)?.to_rgba8();
+    Ok(Drawing {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        pixels: image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                Color::from_rgba8(r, g, b, a)
+            })
+            .collect(),
+    })
+}
+
+/// Load image by its extension, such as `.png`, otherwise drawing file ignoring warnings.
+pub async fn load_drawing_or_image(path: &Path) -> Result<Drawing> {
+    if ImageFormat::from_path(path).is_ok() {
+        load_image(path).await
+    } else {
+        Ok(load_drawing_from_file(path).await?.0)
+    }
+}
+
+/// Load palette from a `.hex` file of one color per line, or the colors of an image or drawing.
+pub async fn load_palette(path: &Path) -> Result<Vec<[u8; 3]>> {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("hex"))
+    {
+        let text = tokio::fs::read_to_string(path).await?;
+        let mut palette = vec![];
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let color = csscolorparser::parse(&format!("#{}", line.trim_start_matches('#')))?;
+            let [r, g, b, _] = color.to_rgba8();
+            palette.push([r, g, b]);
+        }
+        return Ok(palette);
+    }
+    let drawing = load_drawing_or_image(path).await?;
+    let mut palette = drawing.color_counts().into_keys().collect::<Vec<_>>();
+    palette.sort();
+    Ok(palette)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_invalid_files() {
+        let dir = std::env::temp_dir().join(format!("tuisprite-io-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let files = [
+            ("garbage.json", &b"\x00not json"[..]),
+            ("oversized.json", br#"{"width":100000,"height":100000}"#),
+            ("garbage.aseprite", b"not aseprite"),
+        ];
+        for (name, data) in files {
+            let path = dir.join(name);
+            tokio::fs::write(&path, data).await.unwrap();
+            assert!(load_drawing_from_file(&path).await.is_err(), "{}", name);
+        }
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
 }
@@ -0,0 +1,55 @@
+//! Pixel lines for strokes.
+
+/// Pixels of the line from `from` to `to` by Bresenham's algorithm, both ends included.
+pub fn line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+    let (x1, y1) = (to.0 as isize, to.1 as isize);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut points = vec![];
+    loop {
+        points.push((x as usize, y as usize));
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+    points
+}
+
+/// Whether `b` is the corner of an L shape formed by `a`, `b` and `c`,
+/// which pixel-perfect strokes remove.
+pub fn is_corner(a: (usize, usize), b: (usize, usize), c: (usize, usize)) -> bool {
+    (a.0 == b.0 || a.1 == b.1) && (b.0 == c.0 || b.1 == c.1) && a.0 != c.0 && a.1 != c.1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        assert_eq!(line((0, 0), (0, 0)), vec![(0, 0)]);
+        assert_eq!(line((0, 0), (3, 1)), vec![(0, 0), (1, 0), (2, 1), (3, 1)]);
+        assert_eq!(line((2, 2), (0, 0)), vec![(2, 2), (1, 1), (0, 0)]);
+    }
+
+    #[test]
+    fn test_is_corner() {
+        assert!(is_corner((0, 0), (1, 0), (1, 1)));
+        assert!(!is_corner((0, 0), (1, 0), (2, 0)));
+        assert!(!is_corner((0, 0), (1, 1), (2, 2)));
+    }
+}
@@ -3,10 +3,24 @@
 use csscolorparser::Color;
 use serde::{Deserialize, Serialize};
 
+use crate::drawing::transform::Anchor;
+
+pub mod aseprite;
 pub mod color;
+pub mod dither;
+#[cfg(test)]
+mod fixtures;
+pub mod format;
 pub mod io;
+pub mod line;
+pub mod quantize;
+pub mod scale;
+pub mod transform;
+
+/// Largest width or height of a drawing, to keep memory bounded.
+pub const MAX_SIZE: usize = 4096;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Drawing {
     pub width: usize,
     pub height: usize,
@@ -26,11 +40,14 @@ impl Drawing {
     }
 
     pub fn validate(&mut self) -> bool {
+        let Some(len) = self.width.checked_mul(self.height) else {
+            return false;
+        };
         // XXX: should not modify
         if self.pixels.is_empty() {
-            self.pixels = vec![Color::from_rgba8(0, 0, 0, 0); self.width * self.height];
+            self.pixels = vec![Color::from_rgba8(0, 0, 0, 0); len];
         }
-        self.pixels.len() == self.width * self.height
+        self.pixels.len() == len
     }
 }
 
@@ -60,15 +77,21 @@ impl Drawing {
         self.pixels.get_mut(index)
     }
 
-    pub fn resize(&mut self, width: usize, height: usize) {
+    /// Change the canvas size without scaling, keeping the `anchor` point in place.
+    pub fn resize(&mut self, width: usize, height: usize, anchor: Anchor) {
         let old_width = self.width;
         let old_height = self.height;
         let mut new_pixels = Vec::with_capacity(width * height);
+        let dx = Anchor::offset(anchor.0, old_width, width);
+        let dy = Anchor::offset(anchor.1, old_height, height);
 
         for y in 0..height {
             for x in 0..width {
-                let color = if y < old_height && x < old_width {
-                    let old_index = y * old_width + x;
+                let (old_x, old_y) = (x as isize - dx, y as isize - dy);
+                let color = if (0..old_width as isize).contains(&old_x)
+                    && (0..old_height as isize).contains(&old_y)
+                {
+                    let old_index = old_y as usize * old_width + old_x as usize;
                     self.pixels[old_index].clone()
                 } else {
                     Color::from_rgba8(0, 0, 0, 0)
@@ -83,6 +106,50 @@ impl Drawing {
         self.pixels = new_pixels;
     }
 
+    /// Positions of the 4-connected area of the same color containing `(x, y)`.
+    pub fn region(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let Some(target) = self.pixel(x, y) else {
+            return vec![];
+        };
+        let mut visited = vec![false; self.width * self.height];
+        let mut stack = vec![(x, y)];
+        let mut region = vec![];
+        while let Some((x, y)) = stack.pop() {
+            if visited[y * self.width + x] || self.pixel(x, y) != Some(target) {
+                continue;
+            }
+            visited[y * self.width + x] = true;
+            region.push((x, y));
+
+            if x > 0 {
+                stack.push((x - 1, y));
+            }
+            if y > 0 {
+                stack.push((x, y - 1));
+            }
+            if x + 1 < self.width {
+                stack.push((x + 1, y));
+            }
+            if y + 1 < self.height {
+                stack.push((x, y + 1));
+            }
+        }
+        region
+    }
+
+    /// Copy of the given region, parts outside of the drawing are transparent.
+    pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Drawing {
+        let mut cropped = Drawing::new(width, height);
+        for dy in 0..height {
+            for dx in 0..width {
+                if let Some(color) = self.pixel(x + dx, y + dy) {
+                    cropped.pixels[dy * width + dx] = color.clone();
+                }
+            }
+        }
+        cropped
+    }
+
     pub fn erase_all(&mut self) {
         self.pixels = vec![Color::from_rgba8(0, 0, 0, 0); self.width * self.height];
     }
@@ -0,0 +1,262 @@
+//! Color reduction.
+
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::bail;
+use csscolorparser::Color;
+
+use crate::{drawing::Drawing, utils::color_depth::nearest};
+
+/// Rounds of k-means refinement at most.
+const K_MEANS_ITERATIONS: usize = 16;
+
+/// How a palette is chosen for a drawing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Method {
+    #[default]
+    MedianCut,
+    /// Median cut refined by k-means, slower but closer to the original.
+    KMeans,
+}
+
+impl FromStr for Method {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mediancut" => Ok(Self::MedianCut),
+            "kmeans" => Ok(Self::KMeans),
+            _ => bail!("unknown quantize method: {}", s),
+        }
+    }
+}
+
+/// Palette of at most `max` colors by median cut, over colors with their pixel counts.
+pub fn median_cut(colors: &HashMap<[u8; 3], usize>, max: usize) -> Vec<[u8; 3]> {
+    let mut boxes = vec![colors.iter().map(|(c, n)| (*c, *n)).collect::<Vec<_>>()];
+    while boxes.len() < max {
+        // split the box with the widest channel range
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .flat_map(|(i, b)| (0..3).map(move |ch| (i, ch, range(b, ch))))
+            .max_by_key(|(_, _, range)| *range)
+        else {
+            break;
+        };
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|(c, _)| c[channel]);
+        // median by pixel count, keeping both halves non-empty
+        let total = colors.iter().map(|(_, n)| n).sum::<usize>();
+        let mut count = 0;
+        let split = colors
+            .iter()
+            .position(|(_, n)| {
+                count += n;
+                count * 2 >= total
+            })
+            .unwrap_or(0)
+            .clamp(0, colors.len() - 2)
+            + 1;
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|b| average(b)).collect()
+}
+
+/// Palette of at most `max` colors by k-means, starting from the median cut palette.
+pub fn k_means(colors: &HashMap<[u8; 3], usize>, max: usize) -> Vec<[u8; 3]> {
+    let mut palette = median_cut(colors, max);
+    for _ in 0..K_MEANS_ITERATIONS {
+        let mut clusters = vec![vec![]; palette.len()];
+        for (color, n) in colors {
+            clusters[nearest(&palette, *color)].push((*color, *n));
+        }
+        let next = clusters
+            .iter()
+            .zip(&palette)
+            .map(|(cluster, old)| {
+                if cluster.is_empty() {
+                    *old
+                } else {
+                    average(cluster)
+                }
+            })
+            .collect::<Vec<_>>();
+        if next == palette {
+            break;
+        }
+        palette = next;
+    }
+    palette
+}
+
+fn range(colors: &[([u8; 3], usize)], channel: usize) -> u8 {
+    let values = colors.iter().map(|(c, _)| c[channel]);
+    values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+}
+
+/// Average weighted by pixel count.
+fn average(colors: &[([u8; 3], usize)]) -> [u8; 3] {
+    let total = colors.iter().map(|(_, n)| n).sum::<usize>().max(1);
+    let mut sum = [0; 3];
+    for (color, n) in colors {
+        for ch in 0..3 {
+            sum[ch] += color[ch] as usize * n;
+        }
+    }
+    sum.map(|s| ((s + total / 2) / total) as u8)
+}
+
+impl Drawing {
+    /// Colors of visible pixels with their pixel counts.
+    pub fn color_counts(&self) -> HashMap<[u8; 3], usize> {
+        let mut colors = HashMap::new();
+        for color in &self.pixels {
+            let [r, g, b, a] = color.to_rgba8();
+            if a > 0 {
+                *colors.entry([r, g, b]).or_insert(0) += 1;
+            }
+        }
+        colors
+    }
+
+    /// Reduce to at most `max` colors, returning the number of changed pixels.
+    pub fn quantize(&mut self, max: usize, method: Method) -> usize {
+        let colors = self.color_counts();
+        let palette = match method {
+            Method::MedianCut => median_cut(&colors, max),
+            Method::KMeans => k_means(&colors, max),
+        };
+        self.remap(&palette, false)
+    }
+
+    /// Replace visible pixels by the nearest palette color, keeping alpha,
+    /// with Floyd–Steinberg dithering when `dither`.
+    ///
+    /// Returns the number of changed pixels.
+    pub fn remap(&mut self, palette: &[[u8; 3]], dither: bool) -> usize {
+        if palette.is_empty() {
+            return 0;
+        }
+        let (width, height) = (self.width, self.height);
+        let mut errors = vec![[0f32; 3]; width * height];
+        let mut changed = 0;
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                let [r, g, b, a] = self.pixels[index].to_rgba8();
+                if a == 0 {
+                    continue;
+                }
+                let rgb = [r, g, b];
+                let wanted =
+                    [0, 1, 2].map(|ch| (rgb[ch] as f32 + errors[index][ch]).clamp(0., 255.));
+                let target = wanted.map(|c| c.round() as u8);
+                let color = palette[nearest(palette, target)];
+                if color != rgb {
+                    self.pixels[index] = Color::from_rgba8(color[0], color[1], color[2], a);
+                    changed += 1;
+                }
+                if !dither {
+                    continue;
+                }
+                let error = [0, 1, 2].map(|ch| wanted[ch] - color[ch] as f32);
+                for (dx, dy, weight) in [(1, 0, 7.), (-1, 1, 3.), (0, 1, 5.), (1, 1, 1.)] {
+                    if let Some(x) = x.checked_add_signed(dx)
+                        && x < width
+                        && y + dy < height
+                    {
+                        let neighbor = &mut errors[(y + dy) * width + x];
+                        for ch in 0..3 {
+                            neighbor[ch] += error[ch] * weight / 16.;
+                        }
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::drawing::fixtures::rgba;
+
+    #[test]
+    fn test_median_cut() {
+        let colors = HashMap::from([
+            ([0, 0, 0], 1),
+            ([10, 0, 0], 1),
+            ([250, 0, 0], 1),
+            ([255, 0, 0], 1),
+        ]);
+        let mut palette = median_cut(&colors, 2);
+        palette.sort();
+        assert_eq!(palette, [[5, 0, 0], [253, 0, 0]]);
+        assert_eq!(nearest(&palette, [200, 10, 0]), 1);
+
+        // no more colors than there are
+        assert_eq!(median_cut(&colors, 16).len(), 4);
+    }
+
+    #[test]
+    fn test_k_means() {
+        let colors = HashMap::from([
+            ([0, 0, 0], 10),
+            ([20, 0, 0], 10),
+            ([100, 0, 0], 1),
+            ([255, 0, 0], 10),
+        ]);
+        let mut palette = k_means(&colors, 2);
+        palette.sort();
+        // the outlier moves from the red to the dark cluster
+        assert_eq!(palette, [[14, 0, 0], [255, 0, 0]]);
+    }
+
+    #[test]
+    fn test_remap() {
+        let mut drawing = Drawing::new(4, 1);
+        drawing.pixels = vec![
+            Color::from_rgba8(10, 10, 10, 255),
+            Color::from_rgba8(250, 250, 250, 128),
+            Color::from_rgba8(255, 255, 255, 255),
+            Color::from_rgba8(0, 0, 0, 0),
+        ];
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        assert_eq!(drawing.remap(&palette, false), 2);
+        assert_eq!(
+            rgba(&drawing),
+            [
+                [0, 0, 0, 255],
+                [255, 255, 255, 128],
+                [255, 255, 255, 255],
+                [0, 0, 0, 0]
+            ]
+        );
+
+        // dithered gray mixes black and white half and half
+        let mut drawing = Drawing::new(4, 4);
+        drawing.pixels = vec![Color::from_rgba8(128, 128, 128, 255); 16];
+        assert_eq!(drawing.remap(&palette, true), 16);
+        let white = drawing.color_counts()[&[255, 255, 255]];
+        assert_eq!(white, 8);
+    }
+
+    #[test]
+    fn test_quantize() {
+        let mut drawing = Drawing::new(3, 1);
+        drawing.pixels = vec![
+            Color::from_rgba8(0, 0, 0, 255),
+            Color::from_rgba8(10, 0, 0, 255),
+            Color::from_rgba8(255, 0, 0, 255),
+        ];
+        assert_eq!(drawing.quantize(3, Method::MedianCut), 0);
+        assert_eq!(drawing.quantize(2, Method::KMeans), 2);
+        assert_eq!(drawing.color_counts().len(), 2);
+    }
+}
@@ -0,0 +1,302 @@
+//! Resampling drawings to another size.
+
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use csscolorparser::Color;
+
+use crate::drawing::{Drawing, MAX_SIZE};
+
+/// Resampling algorithm.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Filter {
+    /// Repeat or drop pixels.
+    #[default]
+    Nearest,
+    /// EPX doubling, which smooths diagonal edges of pixel art.
+    #[value(alias = "epx")]
+    Scale2x,
+    /// EPX tripling.
+    Scale3x,
+    /// Average the covered pixels, for downscaling.
+    Box,
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match <Filter as ValueEnum>::from_str(s, true) {
+            Ok(filter) => Ok(filter),
+            Err(_) => bail!("unknown filter: {}", s),
+        }
+    }
+}
+
+/// Target size of resampling, a factor like `2` or `0.5`, or exact size like `32x16`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Size {
+    Factor(f32),
+    Exact(usize, usize),
+}
+
+impl Size {
+    /// Target size for a drawing of `width` x `height`, at least one pixel and at most [`MAX_SIZE`].
+    pub fn resolve(self, width: usize, height: usize) -> Result<(usize, usize)> {
+        let (width, height) = match self {
+            Size::Factor(factor) => (
+                ((width as f32 * factor).round() as usize).max(1),
+                ((height as f32 * factor).round() as usize).max(1),
+            ),
+            Size::Exact(width, height) => (width, height),
+        };
+        if width > MAX_SIZE || height > MAX_SIZE {
+            bail!(
+                "size {}x{} exceeds the maximum {}x{}",
+                width,
+                height,
+                MAX_SIZE,
+                MAX_SIZE
+            );
+        }
+        Ok((width, height))
+    }
+}
+
+impl FromStr for Size {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((width, height)) = s.split_once('x') {
+            match (width.parse(), height.parse()) {
+                (Ok(width), Ok(height)) if width > 0 && height > 0 => {
+                    return Ok(Size::Exact(width, height));
+                }
+                _ => bail!("invalid size: {}", s),
+            }
+        }
+        match s.parse::<f32>() {
+            Ok(factor) if factor > 0. && factor.is_finite() => Ok(Size::Factor(factor)),
+            _ => bail!("scale should be a factor like 2 or a size like 32x16"),
+        }
+    }
+}
+
+/// Resampling.
+impl Drawing {
+    /// Resample to `width` x `height`.
+    ///
+    /// Scale2x and Scale3x are applied while they fit in the size,
+    /// then the rest is done by nearest neighbour.
+    pub fn scale(&self, width: usize, height: usize, filter: Filter) -> Drawing {
+        let pixels = self.pixels.iter().map(Color::to_rgba8).collect::<Vec<_>>();
+        let (mut pixels, mut w, mut h) = (pixels, self.width, self.height);
+        let pixels = match filter {
+            Filter::Nearest => nearest(&pixels, (w, h), (width, height)),
+            Filter::Box => box_filter(&pixels, (w, h), (width, height)),
+            Filter::Scale2x | Filter::Scale3x => {
+                let factor = if filter == Filter::Scale2x { 2 } else { 3 };
+                while w * factor <= width && h * factor <= height {
+                    pixels = epx(&pixels, w, h, factor);
+                    (w, h) = (w * factor, h * factor);
+                }
+                nearest(&pixels, (w, h), (width, height))
+            }
+        };
+        Drawing {
+            width,
+            height,
+            pixels: pixels
+                .into_iter()
+                .map(|[r, g, b, a]| Color::from_rgba8(r, g, b, a))
+                .collect(),
+        }
+    }
+}
+
+type Rgba = [u8; 4];
+
+fn nearest(pixels: &[Rgba], from: (usize, usize), to: (usize, usize)) -> Vec<Rgba> {
+    let mut scaled = Vec::with_capacity(to.0 * to.1);
+    for y in 0..to.1 {
+        for x in 0..to.0 {
+            let (sx, sy) = (x * from.0 / to.0, y * from.1 / to.1);
+            scaled.push(pixels[sy * from.0 + sx]);
+        }
+    }
+    scaled
+}
+
+/// Average of source pixels covered by each target pixel, weighted by alpha.
+fn box_filter(pixels: &[Rgba], from: (usize, usize), to: (usize, usize)) -> Vec<Rgba> {
+    // source range covered by target pixel `i`, at least one pixel
+    let range = |i: usize, from: usize, to: usize| {
+        let start = i * from / to;
+        start..((i + 1) * from).div_ceil(to).max(start + 1)
+    };
+
+    let mut scaled = Vec::with_capacity(to.0 * to.1);
+    for y in 0..to.1 {
+        for x in 0..to.0 {
+            let mut sum = [0.; 4];
+            let mut count = 0.;
+            for sy in range(y, from.1, to.1) {
+                for sx in range(x, from.0, to.0) {
+                    let [r, g, b, a] = pixels[sy * from.0 + sx].map(f32::from);
+                    let alpha = a / 255.;
+                    sum[0] += r * alpha;
+                    sum[1] += g * alpha;
+                    sum[2] += b * alpha;
+                    sum[3] += a;
+                    count += 1.;
+                }
+            }
+            let alpha = sum[3] / 255.;
+            let color = if alpha > 0. {
+                [
+                    sum[0] / alpha,
+                    sum[1] / alpha,
+                    sum[2] / alpha,
+                    sum[3] / count,
+                ]
+            } else {
+                [0.; 4]
+            };
+            scaled.push(color.map(|c| c.round().clamp(0., 255.) as u8));
+        }
+    }
+    scaled
+}
+
+/// Scale2x (`factor` 2) or Scale3x (`factor` 3), also known as EPX.
+fn epx(pixels: &[Rgba], width: usize, height: usize, factor: usize) -> Vec<Rgba> {
+    // neighbour at offset, edges are extended
+    let at = |x: usize, y: usize, dx: isize, dy: isize| {
+        let x = x.saturating_add_signed(dx).min(width - 1);
+        let y = y.saturating_add_signed(dy).min(height - 1);
+        pixels[y * width + x]
+    };
+
+    let new_width = width * factor;
+    let mut scaled = vec![[0; 4]; new_width * height * factor];
+    for y in 0..height {
+        for x in 0..width {
+            let [a, b, c, d, e, f, g, h, i] = [
+                at(x, y, -1, -1),
+                at(x, y, 0, -1),
+                at(x, y, 1, -1),
+                at(x, y, -1, 0),
+                at(x, y, 0, 0),
+                at(x, y, 1, 0),
+                at(x, y, -1, 1),
+                at(x, y, 0, 1),
+                at(x, y, 1, 1),
+            ];
+            let block = if factor == 2 {
+                vec![
+                    if d == b && d != h && b != f { b } else { e },
+                    if b == f && b != d && f != h { f } else { e },
+                    if d == h && d != b && h != f { d } else { e },
+                    if h == f && h != d && f != b { h } else { e },
+                ]
+            } else {
+                let db = d == b && b != f && d != h;
+                let bf = b == f && b != d && f != h;
+                let dh = d == h && d != b && h != f;
+                let hf = h == f && d != h && b != f;
+                vec![
+                    if db { d } else { e },
+                    if (db && e != c) || (bf && e != a) {
+                        b
+                    } else {
+                        e
+                    },
+                    if bf { f } else { e },
+                    if (db && e != g) || (dh && e != a) {
+                        d
+                    } else {
+                        e
+                    },
+                    e,
+                    if (bf && e != i) || (hf && e != c) {
+                        f
+                    } else {
+                        e
+                    },
+                    if dh { d } else { e },
+                    if (dh && e != i) || (hf && e != g) {
+                        h
+                    } else {
+                        e
+                    },
+                    if hf { f } else { e },
+                ]
+            };
+            for (k, color) in block.into_iter().enumerate() {
+                let (bx, by) = (k % factor, k / factor);
+                scaled[(y * factor + by) * new_width + x * factor + bx] = color;
+            }
+        }
+    }
+    scaled
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::drawing::fixtures::rgba;
+
+    const BLACK: Rgba = [0, 0, 0, 255];
+    const WHITE: Rgba = [255, 255, 255, 255];
+
+    fn drawing(width: usize, height: usize, pixels: &[Rgba]) -> Drawing {
+        Drawing {
+            width,
+            height,
+            pixels: pixels
+                .iter()
+                .map(|&[r, g, b, a]| Color::from_rgba8(r, g, b, a))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_scale2x_rounds_corner() {
+        let corner = drawing(2, 2, &[BLACK, BLACK, BLACK, WHITE]);
+        let scaled = corner.scale(4, 4, Filter::Scale2x);
+        assert_eq!(rgba(&scaled)[2 * 4 + 2], BLACK);
+        assert_eq!(rgba(&scaled)[3 * 4 + 3], WHITE);
+
+        let nearest = corner.scale(4, 4, Filter::Nearest);
+        assert_eq!(rgba(&nearest)[2 * 4 + 2], WHITE);
+    }
+
+    #[test]
+    fn test_scale3x_keeps_flat_area() {
+        let flat = drawing(2, 1, &[WHITE, WHITE]);
+        let scaled = flat.scale(6, 3, Filter::Scale3x);
+        assert!(rgba(&scaled).iter().all(|c| *c == WHITE));
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(
+            "0.5".parse::<Size>().unwrap().resolve(16, 3).unwrap(),
+            (8, 2)
+        );
+        assert!("1000".parse::<Size>().unwrap().resolve(16, 3).is_err());
+        assert_eq!("32x8".parse::<Size>().unwrap(), Size::Exact(32, 8));
+        assert!("0x8".parse::<Size>().is_err());
+        assert!("-1".parse::<Size>().is_err());
+    }
+
+    #[test]
+    fn test_box_downscale() {
+        let transparent = [0, 0, 0, 0];
+        let scaled = drawing(2, 1, &[WHITE, transparent]).scale(1, 1, Filter::Box);
+        assert_eq!(rgba(&scaled), [[255, 255, 255, 128]]);
+        let scaled = drawing(2, 1, &[WHITE, BLACK]).scale(1, 1, Filter::Box);
+        assert_eq!(rgba(&scaled), [[128, 128, 128, 255]]);
+    }
+}
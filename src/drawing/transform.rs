@@ -0,0 +1,210 @@
+//! Geometric transformations of drawings.
+
+use std::str::FromStr;
+
+use anyhow::{Result, bail};
+use csscolorparser::Color;
+
+use crate::drawing::{Drawing, scale::Filter};
+
+/// A transformation of the whole drawing or a selected region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transform {
+    /// Mirror left and right.
+    FlipHorizontal,
+    /// Mirror top and bottom.
+    FlipVertical,
+    /// Rotate clockwise by quarter turns.
+    Rotate(usize),
+    /// Move by pixels, wrapping around the edges.
+    Shift(isize, isize),
+    /// Resample to width and height.
+    Scale(usize, usize, Filter),
+}
+
+impl Transform {
+    /// Parse `:flip h|v`.
+    pub fn flip(axis: &str) -> Result<Self> {
+        Ok(match axis {
+            "h" | "horizontal" => Transform::FlipHorizontal,
+            "v" | "vertical" => Transform::FlipVertical,
+            _ => bail!("unknown flip axis: {}", axis),
+        })
+    }
+
+    /// Parse `:rotate 90|180|270`, clockwise degrees.
+    pub fn rotate(degrees: &str) -> Result<Self> {
+        Ok(match degrees {
+            "90" => Transform::Rotate(1),
+            "180" => Transform::Rotate(2),
+            "270" | "-90" => Transform::Rotate(3),
+            _ => bail!("rotation must be 90, 180 or 270 degrees"),
+        })
+    }
+
+    pub fn apply(self, drawing: &Drawing) -> Drawing {
+        if let Transform::Scale(width, height, filter) = self {
+            return drawing.scale(width, height, filter);
+        }
+        let (width, height) = (drawing.width, drawing.height);
+        let (new_width, new_height) = match self {
+            Transform::Rotate(turns) if turns % 2 == 1 => (height, width),
+            _ => (width, height),
+        };
+
+        let mut transformed = Drawing::new(new_width, new_height);
+        for y in 0..new_height {
+            for x in 0..new_width {
+                // source position of each new pixel
+                let (sx, sy) = match self {
+                    Transform::FlipHorizontal => (width - 1 - x, y),
+                    Transform::FlipVertical => (x, height - 1 - y),
+                    Transform::Rotate(turns) => match turns % 4 {
+                        1 => (y, height - 1 - x),
+                        2 => (width - 1 - x, height - 1 - y),
+                        3 => (width - 1 - y, x),
+                        _ => (x, y),
+                    },
+                    // reduced first, so large offsets do not overflow
+                    Transform::Shift(dx, dy) => (
+                        (x as isize - dx.rem_euclid(width as isize)).rem_euclid(width as isize)
+                            as usize,
+                        (y as isize - dy.rem_euclid(height as isize)).rem_euclid(height as isize)
+                            as usize,
+                    ),
+                    Transform::Scale(..) => unreachable!(),
+                };
+                transformed.pixels[y * new_width + x] = drawing.pixels[sy * width + sx].clone();
+            }
+        }
+        transformed
+    }
+}
+
+/// Point of the drawing kept in place by resizing, as column and row in `0..=2`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Anchor(pub usize, pub usize);
+
+impl Anchor {
+    /// Offset of old pixels in a drawing resized from `old` to `new` length.
+    pub fn offset(position: usize, old: usize, new: usize) -> isize {
+        (new as isize - old as isize) * position as isize / 2
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "top-left" | "nw" => Anchor(0, 0),
+            "top" | "n" => Anchor(1, 0),
+            "top-right" | "ne" => Anchor(2, 0),
+            "left" | "w" => Anchor(0, 1),
+            "center" | "c" => Anchor(1, 1),
+            "right" | "e" => Anchor(2, 1),
+            "bottom-left" | "sw" => Anchor(0, 2),
+            "bottom" | "s" => Anchor(1, 2),
+            "bottom-right" | "se" => Anchor(2, 2),
+            _ => bail!("unknown anchor: {}", s),
+        })
+    }
+}
+
+/// Region ops.
+impl Drawing {
+    /// Make the given region transparent.
+    pub fn clear(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        for dy in 0..height {
+            for dx in 0..width {
+                if let Some(pixel) = self.pixel_mut(x + dx, y + dy) {
+                    *pixel = Color::from_rgba8(0, 0, 0, 0);
+                }
+            }
+        }
+    }
+
+    /// Copy `other` onto the drawing at `(x, y)`, parts outside of the drawing are dropped.
+    pub fn paste(&mut self, other: &Drawing, x: usize, y: usize) {
+        for dy in 0..other.height {
+            for dx in 0..other.width {
+                if let (Some(pixel), Some(color)) =
+                    (self.pixel_mut(x + dx, y + dy), other.pixel(dx, dy))
+                {
+                    *pixel = color.clone();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// 3x2 drawing with pixels of distinct red.
+    fn sample() -> Drawing {
+        let mut drawing = Drawing::new(3, 2);
+        for (i, pixel) in drawing.pixels.iter_mut().enumerate() {
+            *pixel = Color::from_rgba8(i as u8, 0, 0, 255);
+        }
+        drawing
+    }
+
+    fn reds(drawing: &Drawing) -> Vec<u8> {
+        drawing.pixels.iter().map(|c| c.to_rgba8()[0]).collect()
+    }
+
+    #[test]
+    fn test_flip() {
+        let drawing = sample();
+        assert_eq!(
+            reds(&Transform::FlipHorizontal.apply(&drawing)),
+            [2, 1, 0, 5, 4, 3]
+        );
+        assert_eq!(
+            reds(&Transform::FlipVertical.apply(&drawing)),
+            [3, 4, 5, 0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        let rotated = Transform::Rotate(1).apply(&sample());
+        assert_eq!((rotated.width, rotated.height), (2, 3));
+        assert_eq!(reds(&rotated), [3, 0, 4, 1, 5, 2]);
+        let back = Transform::Rotate(3).apply(&rotated);
+        assert_eq!(reds(&back), reds(&sample()));
+        assert_eq!(
+            reds(&Transform::Rotate(2).apply(&sample())),
+            [5, 4, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_resize_anchored() {
+        let mut drawing = sample();
+        drawing.resize(5, 4, "center".parse().unwrap());
+        assert_eq!(reds(&drawing.crop(1, 1, 3, 2)), reds(&sample()));
+        assert_eq!(drawing.pixels[0].a, 0.);
+
+        let mut drawing = sample();
+        drawing.resize(2, 1, "bottom-right".parse().unwrap());
+        assert_eq!(reds(&drawing), [4, 5]);
+    }
+
+    #[test]
+    fn test_shift_and_scale() {
+        assert_eq!(
+            reds(&Transform::Shift(1, 1).apply(&sample())),
+            [5, 3, 4, 2, 0, 1]
+        );
+        assert_eq!(
+            reds(&Transform::Shift(isize::MIN, isize::MAX).apply(&sample())),
+            reds(&Transform::Shift(1, 1).apply(&sample()))
+        );
+        let scaled = Transform::Scale(6, 4, Filter::Nearest).apply(&sample());
+        assert_eq!((scaled.width, scaled.height), (6, 4));
+        assert_eq!(reds(&scaled)[..6], [0, 0, 1, 1, 2, 2]);
+    }
+}
@@ -0,0 +1,142 @@
+//! Animated GIF export.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+
+use crate::{
+    drawing::{Drawing, quantize::median_cut},
+    utils::color_depth::nearest,
+};
+
+/// Pixels less opaque than this are transparent in GIF.
+const ALPHA_THRESHOLD: u8 = 128;
+/// Delay of frames when not given.
+const DEFAULT_DELAY_MS: u32 = 100;
+
+/// Timing of exported frames.
+#[derive(Default)]
+pub struct Animation {
+    /// Delay of each frame in milliseconds, the last one is used for the rest.
+    pub delays: Vec<u32>,
+    /// Times to play again after the first, forever when `None`.
+    pub repeat: Option<u16>,
+}
+
+impl Animation {
+    /// Delay of frame at `index` in milliseconds.
+    pub fn delay(&self, index: usize) -> u32 {
+        self.delays
+            .get(index)
+            .or(self.delays.last())
+            .copied()
+            .unwrap_or(DEFAULT_DELAY_MS)
+    }
+}
+
+/// Encode frames as GIF with a shared palette, with a note when colors were merged.
+pub fn encode_gif(frames: &[Drawing], animation: &Animation) -> Result<(Vec<u8>, Option<String>)> {
+    let Some(first) = frames.first() else {
+        bail!("no frame to export");
+    };
+    if frames
+        .iter()
+        .any(|f| (f.width, f.height) != (first.width, first.height))
+    {
+        bail!("all frames should have the same size");
+    }
+    let (Ok(width), Ok(height)) = (u16::try_from(first.width), u16::try_from(first.height)) else {
+        bail!("drawing too large for GIF");
+    };
+
+    let mut colors = HashMap::new();
+    let mut transparent = false;
+    for color in frames.iter().flat_map(|f| &f.pixels) {
+        let [r, g, b, a] = color.to_rgba8();
+        if a < ALPHA_THRESHOLD {
+            transparent = true;
+        } else {
+            *colors.entry([r, g, b]).or_insert(0) += 1;
+        }
+    }
+    // one index is kept for transparency
+    let max = if transparent { 255 } else { 256 };
+    let (mut palette, note) = if colors.len() > max {
+        let palette = median_cut(&colors, max);
+        let note = format!("{} colors merged into {}", colors.len(), palette.len());
+        (palette, Some(note))
+    } else {
+        (colors.keys().copied().collect::<Vec<_>>(), None)
+    };
+    palette.sort();
+    let transparent_index = transparent.then_some(palette.len() as u8);
+    let mut palette_bytes = palette.concat();
+    if transparent {
+        palette_bytes.extend([0, 0, 0]);
+    }
+
+    let mut data = Vec::new();
+    {
+        let mut encoder = Encoder::new(&mut data, width, height, &palette_bytes)?;
+        match animation.repeat {
+            None => encoder.set_repeat(Repeat::Infinite)?,
+            Some(0) => {}
+            Some(n) => encoder.set_repeat(Repeat::Finite(n))?,
+        }
+
+        // cache of nearest palette index, colors repeat a lot
+        let mut indices = HashMap::new();
+        for (i, drawing) in frames.iter().enumerate() {
+            let buffer = drawing
+                .pixels
+                .iter()
+                .map(|color| {
+                    let [r, g, b, a] = color.to_rgba8();
+                    match transparent_index {
+                        Some(index) if a < ALPHA_THRESHOLD => index,
+                        _ => *indices
+                            .entry([r, g, b])
+                            .or_insert_with(|| nearest(&palette, [r, g, b]) as u8),
+                    }
+                })
+                .collect::<Vec<_>>();
+            let frame = Frame {
+                width,
+                height,
+                buffer: buffer.into(),
+                // in units of 10 ms
+                delay: (animation.delay(i) / 10).min(u16::MAX as u32) as u16,
+                transparent: transparent_index,
+                // transparent pixels should not show previous frames
+                dispose: DisposalMethod::Background,
+                ..Frame::default()
+            };
+            encoder.write_frame(&frame)?;
+        }
+    }
+    Ok((data, note))
+}
+
+#[cfg(test)]
+mod test {
+    use csscolorparser::Color;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_gif() {
+        let mut drawing = Drawing::new(2, 2);
+        drawing.pixels[0] = Color::from_rgba8(255, 0, 0, 255);
+        let (data, note) = encode_gif(&[drawing.clone(), drawing], &Animation::default()).unwrap();
+        assert_eq!(&data[..6], b"GIF89a");
+        assert_eq!(note, None);
+
+        let mut gradient = Drawing::new(300, 1);
+        for (i, pixel) in gradient.pixels.iter_mut().enumerate() {
+            *pixel = Color::from_rgba8((i % 256) as u8, (i / 256) as u8, 0, 255);
+        }
+        let (_, note) = encode_gif(&[gradient], &Animation::default()).unwrap();
+        assert_eq!(note.as_deref(), Some("300 colors merged into 256"));
+    }
+}
@@ -0,0 +1,159 @@
+//! Export of drawings to image formats.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+
+use crate::{
+    cli::ExportArgs,
+    drawing::{Drawing, aseprite, format, io::load_drawing_from_file},
+    export::{
+        gif::{Animation, encode_gif},
+        sheet::{SheetOptions, atlas, pack},
+    },
+};
+
+pub mod gif;
+pub mod sheet;
+
+/// Export from the command line, each input is a frame.
+pub async fn export(args: ExportArgs) -> Result<()> {
+    let mut frames = vec![];
+    for input in &args.inputs {
+        let (mut drawing, warnings) = load_drawing_from_file(input).await?;
+        for warning in warnings {
+            eprintln!("{}: {}", input.display(), warning);
+        }
+        if let Some(size) = args.scale {
+            let (width, height) = size.resolve(drawing.width, drawing.height)?;
+            drawing = drawing.scale(width, height, args.filter);
+        }
+        frames.push(drawing);
+    }
+
+    let animation = Animation {
+        delays: args.delay,
+        repeat: args.repeat,
+    };
+    if let Some(layout) = args.sheet {
+        let options = SheetOptions {
+            layout,
+            columns: args.columns,
+            padding: args.padding,
+            extrude: args.extrude,
+        };
+        return export_sheet(&args.inputs, &frames, &args.output, &animation, &options).await;
+    }
+    let (data, note) = encode(&frames, &args.output, &animation)?;
+    if let Some(note) = note {
+        eprintln!("{}", note);
+    }
+    tokio::fs::write(&args.output, data).await?;
+    Ok(())
+}
+
+/// Write frames packed in PNG at `output`, and the JSON atlas beside it.
+async fn export_sheet(
+    inputs: &[PathBuf],
+    frames: &[Drawing],
+    output: &Path,
+    animation: &Animation,
+    options: &SheetOptions,
+) -> Result<()> {
+    if !output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+    {
+        bail!("spritesheet should be exported as png");
+    }
+    let (sheet, rects) = pack(frames, options);
+
+    let names = inputs
+        .iter()
+        .map(|path| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into()
+        })
+        .collect::<Vec<String>>();
+    let durations = (0..frames.len())
+        .map(|i| animation.delay(i))
+        .collect::<Vec<_>>();
+    let image = output.file_name().unwrap_or_default().to_string_lossy();
+    let atlas = atlas(
+        &names,
+        &rects,
+        &durations,
+        &image,
+        (sheet.width, sheet.height),
+    );
+
+    tokio::fs::write(output, encode_drawing_png(&sheet)?).await?;
+    tokio::fs::write(
+        output.with_extension("json"),
+        serde_json::to_string_pretty(&atlas)?,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Encode frames in the format of `path` extension,
+/// with a note when the conversion lost something.
+pub fn encode(
+    frames: &[Drawing],
+    path: &Path,
+    animation: &Animation,
+) -> Result<(Vec<u8>, Option<String>)> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let single = || match frames {
+        [drawing] => Ok(drawing),
+        _ => bail!(
+            "{} export takes a single frame, use gif for animation",
+            extension
+        ),
+    };
+    Ok(match extension.as_str() {
+        "gif" => encode_gif(frames, animation)?,
+        "ase" | "aseprite" => {
+            let durations = (0..frames.len())
+                .map(|i| animation.delay(i).min(u16::MAX as u32) as u16)
+                .collect::<Vec<_>>();
+            (aseprite::encode(frames, &durations)?, None)
+        }
+        "png" => (encode_drawing_png(single()?)?, None),
+        "json" => (format::encode(single()?)?.into_bytes(), None),
+        _ => bail!("unsupported export format: {:?}", path),
+    })
+}
+
+/// Encode drawing as RGBA PNG.
+fn encode_drawing_png(drawing: &Drawing) -> Result<Vec<u8>> {
+    let bytes = drawing
+        .pixels
+        .iter()
+        .flat_map(|color| color.to_rgba8())
+        .collect::<Vec<_>>();
+    encode_png(drawing.width, drawing.height, png::ColorType::Rgba, &bytes)
+}
+
+/// Encode 8-bit pixel bytes of `color` type as PNG.
+pub fn encode_png(
+    width: usize,
+    height: usize,
+    color: png::ColorType,
+    bytes: &[u8],
+) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width as u32, height as u32);
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(bytes)?;
+    writer.finish()?;
+    Ok(data)
+}
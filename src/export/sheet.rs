@@ -0,0 +1,230 @@
+//! Spritesheet export with JSON atlas in the format of Aseprite.
+
+use clap::ValueEnum;
+use serde_json::{Value, json};
+
+use crate::drawing::Drawing;
+
+/// How frames are arranged in the sheet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// One row.
+    Strip,
+    /// Rows of cells of the largest frame size.
+    Grid,
+    /// Rows of frames sorted by height, for frames of different sizes.
+    Packed,
+}
+
+pub struct SheetOptions {
+    pub layout: Layout,
+    /// Columns of grid layout, about square when `None`.
+    pub columns: Option<usize>,
+    /// Pixels between frames.
+    pub padding: usize,
+    /// Pixels to repeat frame edges by, against bleeding when sampled.
+    pub extrude: usize,
+}
+
+/// Position and size of a frame in the sheet, not including extruded pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Pack frames into one drawing, with the position of each frame.
+pub fn pack(frames: &[Drawing], options: &SheetOptions) -> (Drawing, Vec<FrameRect>) {
+    let extrude = options.extrude;
+    let padding = options.padding;
+    // size taken by each frame
+    let footprint = |frame: &Drawing| (frame.width + 2 * extrude, frame.height + 2 * extrude);
+
+    let mut origins = vec![(0, 0); frames.len()];
+    match options.layout {
+        Layout::Strip => {
+            let mut x = 0;
+            for (origin, frame) in origins.iter_mut().zip(frames) {
+                *origin = (x, 0);
+                x += footprint(frame).0 + padding;
+            }
+        }
+        Layout::Grid => {
+            let columns = options
+                .columns
+                .unwrap_or_else(|| (frames.len() as f32).sqrt().ceil() as usize)
+                .max(1);
+            let cell_width = frames.iter().map(|f| footprint(f).0).max().unwrap_or(0) + padding;
+            let cell_height = frames.iter().map(|f| footprint(f).1).max().unwrap_or(0) + padding;
+            for (i, origin) in origins.iter_mut().enumerate() {
+                *origin = ((i % columns) * cell_width, (i / columns) * cell_height);
+            }
+        }
+        Layout::Packed => {
+            // shelves as wide as a square of all frames
+            let area = frames
+                .iter()
+                .map(|f| (footprint(f).0 + padding) * (footprint(f).1 + padding))
+                .sum::<usize>();
+            let widest = frames.iter().map(|f| footprint(f).0).max().unwrap_or(0);
+            let max_width = ((area as f32).sqrt().ceil() as usize).max(widest);
+
+            let mut order = (0..frames.len()).collect::<Vec<_>>();
+            order.sort_by_key(|&i| std::cmp::Reverse(footprint(&frames[i]).1));
+            let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+            for i in order {
+                let (width, height) = footprint(&frames[i]);
+                if x > 0 && x + width > max_width {
+                    (x, y) = (0, y + shelf_height + padding);
+                    shelf_height = 0;
+                }
+                origins[i] = (x, y);
+                x += width + padding;
+                shelf_height = shelf_height.max(height);
+            }
+        }
+    }
+
+    let width = origins
+        .iter()
+        .zip(frames)
+        .map(|((x, _), f)| x + footprint(f).0)
+        .max()
+        .unwrap_or(0);
+    let height = origins
+        .iter()
+        .zip(frames)
+        .map(|((_, y), f)| y + footprint(f).1)
+        .max()
+        .unwrap_or(0);
+    let mut sheet = Drawing::new(width, height);
+    let mut rects = vec![];
+    for ((x, y), frame) in origins.into_iter().zip(frames) {
+        let (width, height) = footprint(frame);
+        for dy in 0..height {
+            for dx in 0..width {
+                // extruded pixels repeat the nearest edge
+                let sx = dx.saturating_sub(extrude).min(frame.width - 1);
+                let sy = dy.saturating_sub(extrude).min(frame.height - 1);
+                if let (Some(pixel), Some(color)) =
+                    (sheet.pixel_mut(x + dx, y + dy), frame.pixel(sx, sy))
+                {
+                    *pixel = color.clone();
+                }
+            }
+        }
+        rects.push(FrameRect {
+            x: x + extrude,
+            y: y + extrude,
+            width: frame.width,
+            height: frame.height,
+        });
+    }
+    (sheet, rects)
+}
+
+/// Aseprite style JSON atlas, frames as an array.
+pub fn atlas(
+    names: &[String],
+    rects: &[FrameRect],
+    durations: &[u32],
+    image: &str,
+    size: (usize, usize),
+) -> Value {
+    let frames = names
+        .iter()
+        .zip(rects)
+        .zip(durations)
+        .map(|((name, rect), duration)| {
+            json!({
+                "filename": name,
+                "frame": { "x": rect.x, "y": rect.y, "w": rect.width, "h": rect.height },
+                "rotated": false,
+                "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": rect.width, "h": rect.height },
+                "sourceSize": { "w": rect.width, "h": rect.height },
+                "duration": duration,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "frames": frames,
+        "meta": {
+            "app": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "image": image,
+            "format": "RGBA8888",
+            "size": { "w": size.0, "h": size.1 },
+            "scale": "1",
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use csscolorparser::Color;
+
+    use super::*;
+
+    fn options(layout: Layout) -> SheetOptions {
+        SheetOptions {
+            layout,
+            columns: None,
+            padding: 1,
+            extrude: 0,
+        }
+    }
+
+    #[test]
+    fn test_strip_and_grid() {
+        let frames = vec![Drawing::new(2, 3); 3];
+        let (sheet, rects) = pack(&frames, &options(Layout::Strip));
+        assert_eq!((sheet.width, sheet.height), (8, 3));
+        assert_eq!((rects[2].x, rects[2].y), (6, 0));
+
+        let (sheet, rects) = pack(&frames, &options(Layout::Grid));
+        assert_eq!((sheet.width, sheet.height), (5, 7));
+        assert_eq!((rects[2].x, rects[2].y), (0, 4));
+    }
+
+    #[test]
+    fn test_packed_does_not_overlap() {
+        let frames = [(4, 4), (2, 1), (3, 2), (1, 3), (2, 2)]
+            .map(|(w, h)| Drawing::new(w, h))
+            .to_vec();
+        let (sheet, rects) = pack(&frames, &options(Layout::Packed));
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.x + a.width <= sheet.width && a.y + a.height <= sheet.height);
+            for b in &rects[i + 1..] {
+                let apart = a.x + a.width <= b.x
+                    || b.x + b.width <= a.x
+                    || a.y + a.height <= b.y
+                    || b.y + b.height <= a.y;
+                assert!(apart, "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extrude() {
+        let mut frame = Drawing::new(1, 1);
+        frame.pixels[0] = Color::from_rgba8(255, 0, 0, 255);
+        let (sheet, rects) = pack(
+            &[frame],
+            &SheetOptions {
+                extrude: 1,
+                ..options(Layout::Strip)
+            },
+        );
+        assert_eq!((sheet.width, sheet.height), (3, 3));
+        assert_eq!((rects[0].x, rects[0].y), (1, 1));
+        assert!(
+            sheet
+                .pixels
+                .iter()
+                .all(|c| c.to_rgba8() == [255, 0, 0, 255])
+        );
+    }
+}
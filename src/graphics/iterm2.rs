@@ -0,0 +1,22 @@
+//! [iTerm2 inline images protocol](https://iterm2.com/documentation-images.html).
+
+use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::{export::encode_png, graphics::Image};
+
+pub fn encode(image: &Image, columns: u16, rows: u16) -> Result<String> {
+    let png = encode_png(
+        image.width,
+        image.height,
+        png::ColorType::Rgb,
+        &image.to_bytes(),
+    )?;
+    Ok(format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+        png.len(),
+        columns,
+        rows,
+        STANDARD.encode(&png)
+    ))
+}
@@ -0,0 +1,39 @@
+//! [Kitty graphics protocol](https://sw.kovidgoyal.net/kitty/graphics-protocol/).
+
+use std::fmt::Write;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::graphics::Image;
+
+/// Id of the canvas image, so the image is replaced instead of stacked.
+const IMAGE_ID: u32 = 1;
+/// Maximum size of each chunk of base64 data.
+const CHUNK_SIZE: usize = 4096;
+
+/// Delete the canvas image.
+pub const DELETE: &str = "\x1b_Ga=d,d=I,i=1,q=2\x1b\\";
+
+pub fn encode(image: &Image, columns: u16, rows: u16) -> String {
+    let data = STANDARD.encode(image.to_bytes());
+    let chunks = data.as_bytes().chunks(CHUNK_SIZE).collect::<Vec<_>>();
+
+    let mut sequence = String::from(DELETE);
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = (index + 1 < chunks.len()) as u8;
+        if index == 0 {
+            write!(
+                sequence,
+                "\x1b_Ga=T,f=24,s={},v={},c={},r={},i={},C=1,q=2,m={};",
+                image.width, image.height, columns, rows, IMAGE_ID, more
+            )
+            .unwrap();
+        } else {
+            write!(sequence, "\x1b_Gm={};", more).unwrap();
+        }
+        // NOTE: base64 is always ascii
+        sequence.push_str(std::str::from_utf8(chunk).unwrap());
+        sequence.push_str("\x1b\\");
+    }
+    sequence
+}
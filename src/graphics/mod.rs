@@ -0,0 +1,91 @@
+//! Render drawing as true image by terminal graphics protocols.
+
+use std::env;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+pub mod iterm2;
+pub mod kitty;
+pub mod sixel;
+
+/// How the canvas is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Graphics {
+    /// Emulate pixels with half block characters.
+    Halfblocks,
+    /// Kitty graphics protocol.
+    Kitty,
+    Sixel,
+    /// iTerm2 inline images.
+    Iterm2,
+}
+
+impl Graphics {
+    /// Guess supported protocol from environment variables, falling back to half blocks.
+    pub fn detect() -> Self {
+        let term = env::var("TERM").unwrap_or_default();
+        let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+        if term == "xterm-kitty" || term == "xterm-ghostty" || env::var("KITTY_WINDOW_ID").is_ok() {
+            Graphics::Kitty
+        } else if matches!(term_program.as_str(), "iTerm.app" | "WezTerm") {
+            Graphics::Iterm2
+        } else if term.starts_with("foot") || term.starts_with("mlterm") || term.contains("sixel") {
+            Graphics::Sixel
+        } else {
+            Graphics::Halfblocks
+        }
+    }
+
+    /// Escape sequence drawing `image` over `columns` x `rows` cells from cursor position.
+    ///
+    /// Return `None` for half blocks.
+    pub fn encode(self, image: &Image, columns: u16, rows: u16) -> Result<Option<String>> {
+        Ok(match self {
+            Graphics::Halfblocks => None,
+            Graphics::Kitty => Some(kitty::encode(image, columns, rows)),
+            Graphics::Sixel => Some(sixel::encode(image)),
+            Graphics::Iterm2 => Some(iterm2::encode(image, columns, rows)?),
+        })
+    }
+
+    /// Escape sequence removing the drawn image, if the protocol keeps it over text.
+    pub fn clear(self) -> Option<&'static str> {
+        match self {
+            Graphics::Kitty => Some(kitty::DELETE),
+            _ => None,
+        }
+    }
+}
+
+/// Opaque RGB image.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    /// Nearest neighbour upscaling by integer factors.
+    pub fn scale(&self, sx: usize, sy: usize) -> Image {
+        let (width, height) = (self.width * sx, self.height * sy);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(self.pixels[(y / sy) * self.width + x / sx]);
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Pixels as packed RGB bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.pixels.concat()
+    }
+}
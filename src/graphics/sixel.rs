@@ -0,0 +1,127 @@
+//! [Sixel graphics](https://vt100.net/docs/vt3xx-gp/chapter14.html).
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use crate::graphics::Image;
+
+/// Maximum number of color registers.
+const MAX_COLORS: usize = 256;
+
+pub fn encode(image: &Image) -> String {
+    let (palette, indices) = palette(image);
+
+    let mut sequence = format!("\x1bP0;1;0q\"1;1;{};{}", image.width, image.height);
+    for (index, [r, g, b]) in palette.iter().enumerate() {
+        let percent = |x: u8| x as u32 * 100 / 255;
+        write!(
+            sequence,
+            "#{};2;{};{};{}",
+            index,
+            percent(*r),
+            percent(*g),
+            percent(*b)
+        )
+        .unwrap();
+    }
+
+    // each sixel is a column of six pixels
+    for band in (0..image.height).step_by(6) {
+        let rows = band..(band + 6).min(image.height);
+        let colors = rows
+            .clone()
+            .flat_map(|y| &indices[y * image.width..(y + 1) * image.width])
+            .collect::<BTreeSet<_>>();
+
+        for (n, color) in colors.into_iter().enumerate() {
+            if n > 0 {
+                // back to start of the band
+                sequence.push('$');
+            }
+            write!(sequence, "#{}", color).unwrap();
+
+            let mut run = (0, '?');
+            for x in 0..image.width {
+                let bits = rows
+                    .clone()
+                    .filter(|y| indices[y * image.width + x] == *color)
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+                let sixel = (63 + bits) as u8 as char;
+                if sixel == run.1 {
+                    run.0 += 1;
+                } else {
+                    push_run(&mut sequence, run);
+                    run = (1, sixel);
+                }
+            }
+            push_run(&mut sequence, run);
+        }
+        sequence.push('-');
+    }
+
+    sequence.push_str("\x1b\\");
+    sequence
+}
+
+/// Write `count` repeated sixel, with run length encoding.
+fn push_run(sequence: &mut String, (count, sixel): (usize, char)) {
+    if count >= 3 {
+        write!(sequence, "!{}{}", count, sixel).unwrap();
+    } else {
+        for _ in 0..count {
+            sequence.push(sixel);
+        }
+    }
+}
+
+/// Color registers and the register of each pixel.
+///
+/// Exact colors are used when they fit, otherwise a 6x6x6 color cube.
+fn palette(image: &Image) -> (Vec<[u8; 3]>, Vec<usize>) {
+    let mut palette = vec![];
+    let mut lookup = HashMap::new();
+    for pixel in &image.pixels {
+        if !lookup.contains_key(pixel) {
+            lookup.insert(*pixel, palette.len());
+            palette.push(*pixel);
+        }
+    }
+    if palette.len() <= MAX_COLORS {
+        let indices = image.pixels.iter().map(|pixel| lookup[pixel]).collect();
+        return (palette, indices);
+    }
+
+    let level = |x: u8| (x as usize * 5 + 127) / 255;
+    let palette = (0..216)
+        .map(|i| {
+            let value = |l: usize| (l * 255 / 5) as u8;
+            [value(i / 36), value(i / 6 % 6), value(i % 6)]
+        })
+        .collect();
+    let indices = image
+        .pixels
+        .iter()
+        .map(|[r, g, b]| level(*r) * 36 + level(*g) * 6 + level(*b))
+        .collect();
+    (palette, indices)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let image = Image {
+            width: 4,
+            height: 2,
+            pixels: vec![[255, 0, 0]; 8],
+        };
+        assert_eq!(
+            encode(&image),
+            "\x1bP0;1;0q\"1;1;4;2#0;2;100;0;0#0!4B-\x1b\\"
+        );
+    }
+}
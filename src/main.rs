@@ -1,18 +1,26 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{app::App, cli::Args};
+use crate::{
+    app::App,
+    cli::{Args, Command},
+};
 
 mod app;
 mod cli;
 mod drawing;
+mod export;
+mod graphics;
 mod utils;
 mod widgets;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
-    let app = App::new(args.path)?;
+    let mut args = Args::parse();
+    if let Some(Command::Export(args)) = args.command.take() {
+        return export::export(args).await;
+    }
+    let app = App::new(args)?;
 
     let mut terminal = ratatui::init();
     app.run(&mut terminal).await?;
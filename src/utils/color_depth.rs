@@ -0,0 +1,128 @@
+use std::env;
+
+use clap::ValueEnum;
+
+use crate::drawing::dither::bayer;
+
+/// Levels of each channel in xterm 256 color cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Default xterm colors of the 16 ANSI colors.
+const ANSI_16: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// Colors supported by the terminal.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorDepth {
+    /// 24-bit RGB colors.
+    #[default]
+    #[value(name = "truecolor")]
+    TrueColor,
+    /// xterm 256 colors.
+    #[value(name = "256")]
+    Ansi256,
+    /// The 16 ANSI colors.
+    #[value(name = "16")]
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Guess color depth from `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+
+        if matches!(colorterm.as_str(), "truecolor" | "24bit") || term.ends_with("-direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else if term.is_empty() {
+            // NOTE: e.g. Windows Terminal does not set TERM
+            ColorDepth::TrueColor
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// The closest terminal color of `rgb`.
+    pub fn convert(self, rgb: [u8; 3]) -> ratatui::style::Color {
+        match self {
+            ColorDepth::TrueColor => ratatui::style::Color::Rgb(rgb[0], rgb[1], rgb[2]),
+            ColorDepth::Ansi256 => ratatui::style::Color::Indexed(nearest_256(rgb)),
+            ColorDepth::Ansi16 => ratatui::style::Color::Indexed(nearest(&ANSI_16, rgb) as u8),
+        }
+    }
+
+    /// Like [`ColorDepth::convert`], with ordered dithering for pixel at `(x, y)`.
+    pub fn convert_dithered(self, rgb: [u8; 3], x: usize, y: usize) -> ratatui::style::Color {
+        // about the distance between two levels
+        let spread = match self {
+            ColorDepth::TrueColor => return self.convert(rgb),
+            ColorDepth::Ansi256 => 40.,
+            ColorDepth::Ansi16 => 128.,
+        };
+        let offset = (bayer(4, x, y) - 0.5) * spread;
+        self.convert(rgb.map(|v| (v as f32 + offset).clamp(0., 255.) as u8))
+    }
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (*a as i32 - b as i32).pow(2) as u32)
+        .sum()
+}
+
+/// Index of the color closest to `rgb` in `palette`, which must not be empty.
+pub fn nearest(palette: &[[u8; 3]], rgb: [u8; 3]) -> usize {
+    (0..palette.len())
+        .min_by_key(|index| distance(palette[*index], rgb))
+        .unwrap()
+}
+
+/// Closest xterm 256 color, from the color cube or the gray ramp.
+fn nearest_256(rgb: [u8; 3]) -> u8 {
+    let level = |v: u8| nearest(&CUBE_LEVELS.map(|l| [l, 0, 0]), [v, 0, 0]);
+    let [r, g, b] = rgb.map(level);
+    let cube = [CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]];
+
+    let average = rgb.iter().map(|v| *v as u32).sum::<u32>() / 3;
+    let gray_index = (average.saturating_sub(3) / 10).min(23);
+    let gray = [(8 + gray_index * 10) as u8; 3];
+
+    if distance(gray, rgb) < distance(cube, rgb) {
+        232 + gray_index as u8
+    } else {
+        16 + (36 * r + 6 * g + b) as u8
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        use ratatui::style::Color::Indexed;
+
+        assert_eq!(ColorDepth::Ansi256.convert([255, 0, 0]), Indexed(196));
+        assert_eq!(ColorDepth::Ansi256.convert([128, 128, 128]), Indexed(244));
+        assert_eq!(ColorDepth::Ansi16.convert([250, 10, 10]), Indexed(9));
+    }
+}
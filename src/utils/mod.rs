@@ -1 +1,2 @@
+pub mod color_depth;
 pub mod mouse;
@@ -1,12 +1,36 @@
 use std::io;
 
 use anyhow::Result;
+use clap::ValueEnum;
+use crossterm::terminal::WindowSize;
 use ratatui::crossterm::{
     Command,
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
 };
 
+/// Unit of reported mouse position.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MouseMode {
+    /// SGR Pixel mode, the half of cell is known from position.
+    #[default]
+    Pixel,
+    /// SGR cell mode, the half of cell is chosen by toggle or `Alt`.
+    Cell,
+}
+
+impl MouseMode {
+    /// Use pixel mode only when the terminal reports its size in pixels.
+    pub fn detect(window_size: Option<&WindowSize>) -> Self {
+        match window_size {
+            Some(size) if size.width >= size.columns && size.height >= size.rows * 2 => {
+                MouseMode::Pixel
+            }
+            _ => MouseMode::Cell,
+        }
+    }
+}
+
 /// Mouse tracking SGR Pixel mode (1016).
 ///
 /// See:
@@ -22,9 +46,12 @@ impl Command for EnableSgrPixel {
     }
 }
 
-/// Enable mouse and SGR Pixel mode.
-pub fn enable_mouse() -> Result<()> {
-    execute!(io::stdout(), EnableMouseCapture, EnableSgrPixel)?;
+/// Enable mouse, and SGR Pixel mode in pixel mode.
+pub fn enable_mouse(mode: MouseMode) -> Result<()> {
+    execute!(io::stdout(), EnableMouseCapture)?;
+    if mode == MouseMode::Pixel {
+        execute!(io::stdout(), EnableSgrPixel)?;
+    }
     Ok(())
 }
 
@@ -1,24 +1,131 @@
+use std::collections::HashMap;
+
 use ratatui::{layout::Rect, style::Style, widgets::StatefulWidget};
 
 use crate::{
-    app::config::Config,
+    app::config::{Config, mode::Mode},
     drawing::{Drawing, color::ColorExt},
+    graphics::Image,
 };
 
 const UPPER_HALF_BLOCK: &str = "▀";
 const LOWER_HALF_BLOCK: &str = "▄";
+/// Tint of the mirror axis guide.
+const GUIDE_COLOR: [u8; 3] = [255, 0, 255];
+/// Tint of the selection border.
+const SELECTION_COLOR: [u8; 3] = [0, 128, 255];
 
 pub struct Canvas<'a> {
     config: &'a Config,
     drawing: &'a Drawing,
+    /// Pixels covered by the brush under the mouse, with their preview color.
+    ///
+    /// Keyed by drawing position, so the preview is repeated in tile mode.
+    preview: HashMap<(usize, usize), [u8; 3]>,
 }
 
 impl<'a> Canvas<'a> {
-    pub fn new(config: &'a Config, drawing: &'a Drawing) -> Self {
-        Self { config, drawing }
+    pub fn new(config: &'a Config, drawing: &'a Drawing, hover: Option<(usize, usize)>) -> Self {
+        let mut preview = HashMap::new();
+        if let Some((x, y)) = hover {
+            for &((dx, dy), ref stamp) in config.brush.footprint() {
+                if let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) {
+                    let [r, g, b, _] = stamp.as_ref().unwrap_or(&config.color).to_rgba8();
+                    preview.insert(config.wrap(drawing, x, y), [r, g, b]);
+                }
+            }
+        }
+
+        Self {
+            config,
+            drawing,
+            preview,
+        }
+    }
+
+    /// Displayed color of pixel at `(x, y)` over reference and transparency grid, with overlays applied.
+    fn pixel_rgb(&self, x: usize, y: usize) -> Option<[u8; 3]> {
+        let mut bg = self.config.transparency_grid.color_at(x, y);
+        if let Some(reference) = &self.config.reference {
+            bg = reference.blend(bg, x, y);
+        }
+        let color = self.drawing.pixel(x, y)?.to_rgb(bg);
+        if matches!(self.config.mode, Mode::Cursor { .. }) && self.config.cursor == (x, y) {
+            // inverted, so the cursor is visible over any color
+            return Some(color.map(|c| 255 - c));
+        }
+        if let Some(preview) = self.preview.get(&(x, y)) {
+            return Some(tint(color, *preview));
+        }
+        let (width, height) = (self.drawing.width, self.drawing.height);
+        if self.config.mirror.on_guide(x, y, width, height) {
+            return Some(tint(color, GUIDE_COLOR));
+        }
+        if let Some(selection) = self.config.selection
+            && selection.on_edge(x, y)
+        {
+            return Some(tint(color, SELECTION_COLOR));
+        }
+        let grid = &self.config.grid;
+        if grid.on_guide(x, y) {
+            return Some(tint(color, grid.color));
+        }
+        if grid.on_line(x, y) {
+            // lighter than guides
+            return Some(tint(color, tint(color, grid.color)));
+        }
+        Some(color)
+    }
+
+    /// Displayed color at canvas position `(x, y)`, which repeats the drawing in tile mode.
+    fn view_rgb(&self, x: usize, y: usize) -> Option<[u8; 3]> {
+        let (width, height) = self.config.view_size(self.drawing);
+        if x >= width || y >= height {
+            return None;
+        }
+        let (x, y) = self.config.wrap(self.drawing, x, y);
+        self.pixel_rgb(x, y)
+    }
+
+    fn pixel_color(&self, x: usize, y: usize) -> Option<ratatui::style::Color> {
+        let rgb = self.view_rgb(x, y)?;
+        let depth = self.config.color_depth;
+        Some(if self.config.dither_colors {
+            depth.convert_dithered(rgb, x, y)
+        } else {
+            depth.convert(rgb)
+        })
+    }
+
+    /// The displayed canvas as image, one image pixel for each drawing pixel,
+    /// cropped to at most `width` x `height` from the top left.
+    pub fn image(&self, width: usize, height: usize) -> Image {
+        let (view_width, view_height) = self.config.view_size(self.drawing);
+        let (width, height) = (width.min(view_width), height.min(view_height));
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(self.view_rgb(x, y).unwrap_or_default());
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
     }
 }
 
+/// Mix `color` half and half with `tint`.
+fn tint(color: [u8; 3], tint: [u8; 3]) -> [u8; 3] {
+    let mix = |x: u8, y: u8| ((x as u16 + y as u16) / 2) as u8;
+    [
+        mix(color[0], tint[0]),
+        mix(color[1], tint[1]),
+        mix(color[2], tint[2]),
+    ]
+}
+
 impl<'a> StatefulWidget for Canvas<'a> {
     type State = Option<Rect>;
 
@@ -40,20 +147,17 @@ impl<'a> StatefulWidget for Canvas<'a> {
         // 2. only upper has color => upper block
         // 3. only lower has color => lower block
         // 4. none have color => empty
-        for r in 0..self.drawing.height.div_ceil(2) {
-            for c in 0..self.drawing.width {
-                let bg = {
-                    let col = c / self.config.transparency_grid.size;
-                    let row = 2 * r / self.config.transparency_grid.size;
-                    if (col + row).is_multiple_of(2) {
-                        self.config.transparency_grid.dark
-                    } else {
-                        self.config.transparency_grid.light
-                    }
-                };
-
-                let upper = self.drawing.pixel(c, 2 * r);
-                let lower = self.drawing.pixel(c, 2 * r + 1);
+        let (width, height) = self.config.view_size(self.drawing);
+        // the view may be larger than the terminal, like in tile mode
+        let visible = area.intersection(buf.area);
+        let rows = height
+            .div_ceil(2)
+            .min(visible.bottom().saturating_sub(area.y) as usize);
+        let columns = width.min(visible.right().saturating_sub(area.x) as usize);
+        for r in 0..rows {
+            for c in 0..columns {
+                let upper = self.pixel_color(c, 2 * r);
+                let lower = self.pixel_color(c, 2 * r + 1);
 
                 match (upper, lower) {
                     (None, None) => {}
@@ -62,7 +166,7 @@ impl<'a> StatefulWidget for Canvas<'a> {
                             area.x + c as u16,
                             area.y + r as u16,
                             LOWER_HALF_BLOCK,
-                            Style::default().fg(lower.to_ratatui(bg)),
+                            Style::default().fg(lower),
                         );
                     }
                     (Some(upper), None) => {
@@ -70,7 +174,7 @@ impl<'a> StatefulWidget for Canvas<'a> {
                             area.x + c as u16,
                             area.y + r as u16,
                             UPPER_HALF_BLOCK,
-                            Style::default().fg(upper.to_ratatui(bg)),
+                            Style::default().fg(upper),
                         );
                     }
                     (Some(upper), Some(lower)) => {
@@ -78,9 +182,7 @@ impl<'a> StatefulWidget for Canvas<'a> {
                             area.x + c as u16,
                             area.y + r as u16,
                             UPPER_HALF_BLOCK,
-                            Style::default()
-                                .fg(upper.to_ratatui(bg))
-                                .bg(lower.to_ratatui(bg)),
+                            Style::default().fg(upper).bg(lower),
                         );
                     }
                 }
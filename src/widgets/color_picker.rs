@@ -0,0 +1,138 @@
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    widgets::{Block, Clear, StatefulWidget, Widget},
+};
+
+use crate::{
+    app::config::{
+        Config,
+        mode::Mode,
+        picker::{Field, PickerState},
+    },
+    drawing::color::ColorExt,
+};
+
+const UPPER_HALF_BLOCK: &str = "▀";
+const DIALOG_WIDTH: u16 = 44;
+/// Fields, preview and borders.
+const DIALOG_HEIGHT: u16 = Field::ALL.len() as u16 + 3;
+/// Width of value text after sliders.
+const VALUE_WIDTH: u16 = 6;
+
+/// Modal color picker dialog, shown in picker mode.
+pub struct ColorPicker<'a> {
+    config: &'a Config,
+}
+
+impl<'a> ColorPicker<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self { config }
+    }
+
+    /// Background of slider cell, transparency grid for alpha.
+    fn slider_bg(&self, field: Field, col: u16) -> [u8; 3] {
+        let grid = &self.config.transparency_grid;
+        match field {
+            Field::Alpha if col.is_multiple_of(2) => grid.dark,
+            Field::Alpha => grid.light,
+            _ => [0, 0, 0],
+        }
+    }
+
+    fn render_slider(
+        &self,
+        picker: &PickerState,
+        field: Field,
+        area: Rect,
+        buf: &mut ratatui::prelude::Buffer,
+    ) {
+        let marker = (picker.fraction(field) * (area.width - 1) as f32).round() as u16;
+        for col in 0..area.width {
+            let fraction = col as f32 / (area.width - 1).max(1) as f32;
+            let color = picker
+                .slider_color(field, fraction)
+                .to_ratatui(self.slider_bg(field, col), self.config.color_depth);
+            // NOTE: the lower half shows the marker
+            let style = Style::default().fg(color);
+            let style = if col == marker {
+                style.on_white()
+            } else {
+                style.on_black()
+            };
+            buf.set_string(area.x + col, area.y, UPPER_HALF_BLOCK, style);
+        }
+    }
+}
+
+impl<'a> StatefulWidget for ColorPicker<'a> {
+    /// Area of the sliders, one row for each field.
+    type State = Option<Rect>;
+
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+        state: &mut Self::State,
+    ) where
+        Self: Sized,
+    {
+        let Mode::Picker(picker) = &self.config.mode else {
+            return;
+        };
+
+        let width = DIALOG_WIDTH.min(area.width);
+        let height = DIALOG_HEIGHT.min(area.height);
+        let dialog = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        Clear.render(dialog, buf);
+        let block = Block::bordered().title(" Color ");
+        let inner = block.inner(dialog);
+        block.render(dialog, buf);
+        if inner.width <= VALUE_WIDTH + 3 || inner.height < DIALOG_HEIGHT - 2 {
+            return;
+        }
+
+        let bar_width = inner.width - 2 - VALUE_WIDTH;
+        for (row, field) in Field::ALL.into_iter().enumerate() {
+            let y = inner.y + row as u16;
+            let label = Style::default().bold();
+            let label = if field == picker.focus {
+                label.reversed()
+            } else {
+                label
+            };
+            buf.set_string(inner.x, y, field.label(), label);
+
+            let value = picker.value_text(field);
+            if field == Field::Hex {
+                buf.set_string(inner.x + 2, y, &value, Style::default());
+            } else {
+                self.render_slider(picker, field, Rect::new(inner.x + 2, y, bar_width, 1), buf);
+                buf.set_string(inner.x + 3 + bar_width, y, &value, Style::default());
+            }
+        }
+
+        // preview of current and picked color
+        let y = inner.y + Field::ALL.len() as u16;
+        let half = inner.width / 2;
+        let grid = self.config.transparency_grid.light;
+        Block::new()
+            .bg(self.config.color.to_ratatui(grid, self.config.color_depth))
+            .render(Rect::new(inner.x, y, half, 1), buf);
+        Block::new()
+            .bg(picker.color().to_ratatui(grid, self.config.color_depth))
+            .render(Rect::new(inner.x + half, y, inner.width - half, 1), buf);
+
+        *state = Some(Rect::new(
+            inner.x + 2,
+            inner.y,
+            bar_width,
+            Field::ALL.len() as u16 - 1,
+        ));
+    }
+}
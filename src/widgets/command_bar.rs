@@ -35,6 +35,17 @@ impl<'a> StatefulWidget for CommandBar<'a> {
                 format!(":{}", command).render(area, buf);
                 *state = Some((1 + command.width() as u16, area.y));
             }
+            Mode::Picker(_) => {
+                "-- PICKER -- ↑↓ field, ←→ adjust, Enter apply, Esc cancel".render(area, buf);
+            }
+            Mode::Cursor { count, .. } => match (count, self.message) {
+                (Some(count), _) => count.to_string().render(area, buf),
+                (None, Some(message)) => format!("-- {} --", message).render(area, buf),
+                (None, None) => {
+                    "-- CURSOR -- hjkl move, space paint, x erase, v select, y capture brush, Esc exit"
+                        .render(area, buf)
+                }
+            },
         }
     }
 }
@@ -1,4 +1,5 @@
 pub mod canvas;
+pub mod color_picker;
 pub mod command_bar;
 pub mod status_bar;
 pub mod workspace;
@@ -1,40 +1,114 @@
+use std::path::Path;
+
 use ratatui::{
+    layout::Rect,
     style::{Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Widget},
+    widgets::{Block, StatefulWidget, Widget},
 };
 
 use crate::{
-    app::config::{Config, mode::Mode},
-    drawing::color::ColorExt,
+    app::config::{Config, blend::BlendMode, mode::Mode, pattern::Pattern},
+    drawing::{Drawing, color::ColorExt},
+    utils::mouse::MouseMode,
 };
 
 pub struct StatusBar<'a> {
     config: &'a Config,
+    drawing: &'a Drawing,
+    /// Drawing position under the mouse or keyboard cursor.
+    position: Option<(usize, usize)>,
+    path: Option<&'a Path>,
+    /// Whether the drawing has unsaved changes.
+    dirty: bool,
+    /// Whether the opened file failed to load or loaded partially.
+    readonly: bool,
 }
 
 impl<'a> StatusBar<'a> {
-    pub fn new(setting: &'a Config) -> Self {
-        Self { config: setting }
+    pub fn new(
+        setting: &'a Config,
+        drawing: &'a Drawing,
+        position: Option<(usize, usize)>,
+        path: Option<&'a Path>,
+        dirty: bool,
+        readonly: bool,
+    ) -> Self {
+        Self {
+            config: setting,
+            drawing,
+            position,
+            path,
+            dirty,
+            readonly,
+        }
+    }
+
+    /// Drawing info shown on the right, most important first.
+    fn info(&self) -> Vec<Vec<Span<'static>>> {
+        let depth = self.config.color_depth;
+        let mut info = vec![];
+        if let Some((x, y)) = self.position
+            && let Some(color) = self.drawing.pixel(x, y)
+        {
+            info.push(vec![
+                Span::raw(format!("{},{} ", x, y)),
+                Span::styled(
+                    format!(" {} ", color.to_css_hex()),
+                    Style::default()
+                        .bg(color.to_ratatui([0, 0, 0], depth))
+                        .fg(color.calculate_fg().to_ratatui([0, 0, 0], depth)),
+                ),
+            ]);
+        }
+        let name = self
+            .path
+            .and_then(Path::file_name)
+            .map_or("[No Name]".into(), |name| name.to_string_lossy());
+        let dirty = if self.dirty { " [+]" } else { "" };
+        let readonly = if self.readonly { " [RO]" } else { "" };
+        info.push(vec![
+            Span::raw(format!("{}{}{}", name, dirty, readonly)).bold(),
+        ]);
+        info.push(vec![Span::raw(format!(
+            "{}x{}",
+            self.drawing.width, self.drawing.height
+        ))]);
+        info
     }
 }
 
-impl<'a> Widget for StatusBar<'a> {
-    fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer)
-    where
+impl<'a> StatefulWidget for StatusBar<'a> {
+    /// Area of the mirror toggle.
+    type State = Option<Rect>;
+
+    fn render(
+        self,
+        area: ratatui::prelude::Rect,
+        buf: &mut ratatui::prelude::Buffer,
+        state: &mut Self::State,
+    ) where
         Self: Sized,
     {
         // bar background color
         Block::new().on_gray().render(area, buf);
 
-        let bg = self.config.color.to_ratatui([0, 0, 0]);
-        let fg = self.config.color.calculate_fg().to_ratatui([0, 0, 0]);
+        let depth = self.config.color_depth;
+        let bg = self.config.color.to_ratatui([0, 0, 0], depth);
+        let fg = self
+            .config
+            .color
+            .calculate_fg()
+            .to_ratatui([0, 0, 0], depth);
 
         let mut spans = vec![
             Span::raw(" "),
             Span::raw(match &self.config.mode {
                 Mode::Normal => "NORMAL",
                 Mode::Command(_) => "COMMAND",
+                Mode::Picker(_) => "PICKER",
+                Mode::Cursor { anchor: None, .. } => "CURSOR",
+                Mode::Cursor { .. } => "VISUAL",
             })
             .bold(),
             Span::raw(" "),
@@ -43,20 +117,84 @@ impl<'a> Widget for StatusBar<'a> {
                 Style::default().bg(bg).fg(fg),
             )
             .bold(),
+            Span::styled(
+                format!(" {} ", self.config.secondary_color.to_css_hex()),
+                Style::default()
+                    .bg(self.config.secondary_color.to_ratatui([0, 0, 0], depth))
+                    .fg(self
+                        .config
+                        .secondary_color
+                        .calculate_fg()
+                        .to_ratatui([0, 0, 0], depth)),
+            ),
         ];
 
+        let mut history = vec![];
         for (idx, color) in self.config.color_history.iter().rev().enumerate() {
-            let fg = color.calculate_fg().to_ratatui([0, 0, 0]);
-            spans.push(Span::styled(
+            let fg = color.calculate_fg().to_ratatui([0, 0, 0], depth);
+            history.push(Span::styled(
                 format!("{}", to_superscript(idx + 1)),
                 Style::default()
-                    .bg(color.to_ratatui([0, 0, 0]))
+                    .bg(color.to_ratatui([0, 0, 0], depth))
                     .fg(fg)
                     .bold(),
             ));
         }
 
+        let mut tool = vec![Span::raw(format!(" {} ", self.config.tool.label()))];
+        tool.push(Span::raw(format!("{} ", self.config.brush.label())));
+        if self.config.blend != BlendMode::Over {
+            tool.push(Span::raw(format!("{} ", self.config.blend.label())));
+        }
+        if self.config.pattern != Pattern::Solid {
+            tool.push(Span::raw(format!("{} ", self.config.pattern.label())));
+        }
+        if self.config.mouse_mode == MouseMode::Cell {
+            tool.push(Span::raw(if self.config.lower_half {
+                "▄ "
+            } else {
+                "▀ "
+            }));
+        }
+        if self.config.tile {
+            tool.push(Span::raw("TILE "));
+        }
+        if self.config.pixel_perfect {
+            tool.push(Span::raw("PP "));
+        }
+        let mirror = Span::raw(format!(" ◫ {} ", self.config.mirror.mode.label())).on_white();
+
+        // drop history swatches, then drawing info, when the terminal is narrow
+        let width = |spans: &[Span]| spans.iter().map(Span::width).sum::<usize>();
+        // with the trailing space after drawing info
+        let mut used = width(&spans) + width(&tool) + mirror.width() + 1;
+        if used + width(&history) <= area.width as usize {
+            used += width(&history);
+            spans.extend(history);
+        }
+        spans.extend(tool);
+
+        let offset = width(&spans) as u16;
+        *state =
+            Some(Rect::new(area.x + offset, area.y, mirror.width() as u16, 1).intersection(area));
+        spans.push(mirror);
         Line::from(spans).black().render(area, buf);
+
+        let mut info = vec![];
+        for item in self.info() {
+            // separated by a space from the left part and each other
+            let item_width = width(&item) + 1;
+            if used + item_width > area.width as usize {
+                continue;
+            }
+            used += item_width;
+            if !info.is_empty() {
+                info.push(Span::raw(" "));
+            }
+            info.extend(item);
+        }
+        info.push(Span::raw(" "));
+        Line::from(info).black().right_aligned().render(area, buf);
     }
 }
 
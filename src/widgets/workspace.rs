@@ -8,11 +8,16 @@ use crate::{app::config::Config, drawing::Drawing, widgets::canvas::Canvas};
 pub struct Workspace<'a> {
     config: &'a Config,
     drawing: &'a Drawing,
+    hover: Option<(usize, usize)>,
 }
 
 impl<'a> Workspace<'a> {
-    pub fn new(config: &'a Config, drawing: &'a Drawing) -> Self {
-        Self { config, drawing }
+    pub fn new(config: &'a Config, drawing: &'a Drawing, hover: Option<(usize, usize)>) -> Self {
+        Self {
+            config,
+            drawing,
+            hover,
+        }
     }
 }
 
@@ -27,12 +32,14 @@ impl<'a> StatefulWidget for Workspace<'a> {
     ) where
         Self: Sized,
     {
-        let margin_x = (area.width - self.drawing.width as u16) / 2;
+        let (width, height) = self.config.view_size(self.drawing);
+        let cells = |len: usize| u16::try_from(len).unwrap_or(u16::MAX);
+        let margin_x = area.width.saturating_sub(cells(width)) / 2;
         // NOTE: two drawing cell take one height
-        let margin_y = (area.height - self.drawing.height as u16 / 2) / 2;
+        let margin_y = area.height.saturating_sub(cells(height.div_ceil(2))) / 2;
 
         let canvas_area = area.inner(Margin::new(margin_x, margin_y));
 
-        Canvas::new(self.config, self.drawing).render(canvas_area, buf, state);
+        Canvas::new(self.config, self.drawing, self.hover).render(canvas_area, buf, state);
     }
 }